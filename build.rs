@@ -0,0 +1,24 @@
+use std::env;
+use std::process::Command;
+
+// Record details of the build for --version-json
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let rustc_version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".into());
+    println!("cargo:rustc-env=SEVEN_CLOCK_RUSTC={}", rustc_version);
+    for var in ["TARGET", "PROFILE"] {
+        println!(
+            "cargo:rustc-env=SEVEN_CLOCK_{}={}",
+            var,
+            env::var(var).unwrap_or_else(|_| "unknown".into())
+        );
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
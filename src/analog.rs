@@ -0,0 +1,165 @@
+//! Rasterising the face and hands of the analog clock onto character cells.
+
+use std::f64::consts::{PI, TAU};
+
+use time::OffsetDateTime;
+
+/// A character at a column and row of the screen.
+pub type Cell = (u16, u16, char);
+
+/// The largest circle that fits in the rows of the screen it's given. Character cells are
+/// about twice as tall as they are wide, so the circle is twice as many columns wide as it
+/// is rows tall.
+#[derive(Clone, Copy)]
+pub struct Dial {
+    col: f64,
+    row: f64,
+    /// The radius in rows.
+    radius: f64,
+}
+
+impl Dial {
+    /// The dial filling the width of the screen and `rows` rows from `top`.
+    pub fn new(columns: u16, top: u16, rows: u16) -> Self {
+        let (columns, rows) = (
+            f64::from(columns.saturating_sub(1)),
+            f64::from(rows.saturating_sub(1)),
+        );
+        Dial {
+            col: columns / 2.,
+            row: f64::from(top) + rows / 2.,
+            radius: (rows / 2.).min(columns / 4.),
+        }
+    }
+
+    /// The marks around the edge of the face, with the hours larger than the minutes. The
+    /// minutes are only marked when the face is big enough for them not to run together.
+    pub fn face(&self) -> Vec<Cell> {
+        let minutes = if self.radius >= 8. { 60 } else { 0 };
+        let ticks = (0..minutes).filter(|minute| minute % 5 != 0).map(|minute| {
+            let (col, row) = self.cell(f64::from(minute) / 60. * TAU, 1.);
+            (col, row, '·')
+        });
+        let hours = (0..12).map(|hour| {
+            let (col, row) = self.cell(f64::from(hour) / 12. * TAU, 1.);
+            (col, row, if hour % 3 == 0 { '●' } else { '•' })
+        });
+        topmost(ticks.chain(hours))
+    }
+
+    /// The cells of the hands showing `now`, including the second hand if `seconds` is
+    /// true. The hour hand is drawn over the minute hand, which is drawn over the second
+    /// hand.
+    pub fn hands(&self, now: OffsetDateTime, seconds: bool) -> Vec<Cell> {
+        let second = f64::from(now.second());
+        let minute = f64::from(now.minute()) + second / 60.;
+        let hour = f64::from(now.hour() % 12) + minute / 60.;
+        let second_hand = seconds.then(|| self.hand(second / 60. * TAU, 0.9, Some('·')));
+        let cells = second_hand
+            .into_iter()
+            .flatten()
+            .chain(self.hand(minute / 60. * TAU, 0.8, None))
+            .chain(self.hand(hour / 12. * TAU, 0.5, None));
+        let (col, row) = self.cell(0., 0.);
+        topmost(cells.chain([(col, row, '●')]))
+    }
+
+    /// The cells of a hand pointing at `angle` clockwise from 12 o'clock, `length` as a
+    /// fraction of the radius. The hand is drawn with `ch`, or a line in the direction it
+    /// points if `None`.
+    fn hand(&self, angle: f64, length: f64, ch: Option<char>) -> impl Iterator<Item = Cell> {
+        let ch = ch.unwrap_or_else(|| line_char(angle));
+        // At least one step per column so that no cell along the hand is skipped
+        let steps = (self.radius * length * 2.).ceil().max(1.) as u32;
+        let dial = *self;
+        (1..=steps).map(move |step| {
+            let (col, row) = dial.cell(angle, length * f64::from(step) / f64::from(steps));
+            (col, row, ch)
+        })
+    }
+
+    /// The cell `distance` from the centre at `angle` clockwise from 12 o'clock, with
+    /// `distance` as a fraction of the radius.
+    fn cell(&self, angle: f64, distance: f64) -> (u16, u16) {
+        let col = self.col + 2. * self.radius * distance * angle.sin();
+        let row = self.row - self.radius * distance * angle.cos();
+        (col.round().max(0.) as u16, row.round().max(0.) as u16)
+    }
+}
+
+/// The line-drawing character closest to the direction of `angle`.
+fn line_char(angle: f64) -> char {
+    match (angle.rem_euclid(PI) / (PI / 8.)) as u32 {
+        1 | 2 => '╱',
+        3 | 4 => '─',
+        5 | 6 => '╲',
+        _ => '│',
+    }
+}
+
+/// `cells` with only the last of those at the same position kept.
+fn topmost(cells: impl Iterator<Item = Cell>) -> Vec<Cell> {
+    let mut topmost: Vec<Cell> = Vec::new();
+    for cell in cells {
+        topmost.retain(|&(col, row, _)| (col, row) != (cell.0, cell.1));
+        topmost.push(cell);
+    }
+    topmost
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn line_chars() {
+        assert_eq!(line_char(0.), '│');
+        assert_eq!(line_char(PI / 4.), '╱');
+        assert_eq!(line_char(FRAC_PI_2), '─');
+        assert_eq!(line_char(3. * PI / 4.), '╲');
+        // The opposite direction is the same line
+        assert_eq!(line_char(PI), '│');
+        assert_eq!(line_char(5. * PI / 4.), '╱');
+    }
+
+    /// A dial with its centre at column 20, row 10 and a radius of 10 rows.
+    fn dial() -> Dial {
+        Dial::new(41, 0, 21)
+    }
+
+    #[test]
+    fn hands_at_noon() {
+        let hands = dial().hands(datetime!(2024-05-14 12:00 UTC), false);
+        assert!(hands.iter().all(|&(col, _, ch)| col == 20 && ch != '·'));
+        // The tip of the minute hand is 0.8 of the radius above the centre
+        assert_eq!(hands.iter().map(|&(_, row, _)| row).min(), Some(2));
+        assert_eq!(hands.last(), Some(&(20, 10, '●')));
+    }
+
+    #[test]
+    fn hour_hand_at_three() {
+        let hands = dial().hands(datetime!(2024-05-14 15:00 UTC), false);
+        // Half the radius to the right, which is twice as many columns as rows
+        assert!(hands.contains(&(30, 10, '─')));
+        assert!(hands.contains(&(20, 2, '│')));
+    }
+
+    #[test]
+    fn second_hand() {
+        let now = datetime!(2024-05-14 12:00:30 UTC);
+        assert!(dial().hands(now, true).contains(&(20, 19, '·')));
+        assert!(!dial().hands(now, false).iter().any(|&(_, _, ch)| ch == '·'));
+    }
+
+    #[test]
+    fn face_marks() {
+        let face = dial().face();
+        assert!(face.contains(&(20, 0, '●')));
+        assert!(face.contains(&(40, 10, '●')));
+        assert!(face.iter().any(|&(_, _, ch)| ch == '·'));
+    }
+}
@@ -0,0 +1,1995 @@
+//! Parsing the command line arguments into `Options`, and the usage and version text.
+
+use std::fs;
+use std::time::Instant;
+
+use std::ffi::OsStr;
+use std::io::stdout;
+use std::mem;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use crossterm::execute;
+use crossterm::style::{Color, Print, SetBackgroundColor, SetForegroundColor};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+use unicode_width::UnicodeWidthChar;
+
+use crate::colour::{supports_rgb, to_ansi, Theme, THEMES};
+use crate::config::load_config;
+use crate::layout::Anchor;
+use crate::timer::{ChessCfg, PomodoroCfg};
+use crate::tz::{TimeZone, TzError};
+use crate::{json_string, Blink, Error, Font, Gradient, Mode, Moment, Options, Side};
+
+/// The format of `--iso`, RFC 3339 without fractional seconds.
+pub const ISO_FORMAT: &str = "[year]-[month]-[day]T[hour]:[minute]:[second]\
+    [offset_hour sign:mandatory]:[offset_minute]";
+
+/// The length of the breaks of the Pomodoro timer when it's not given.
+const DEFAULT_BREAK: Duration = Duration::from_secs(5 * 60);
+
+/// Parse the argument of `--separator`, which must be a single character one column wide.
+pub fn parse_separator(s: &str) -> Result<char, Error> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.width() == Some(1) => Ok(ch),
+        _ => Err(Error::Message(format!(
+            "invalid separator: '{}', it must be a single character one column wide",
+            s
+        ))),
+    }
+}
+
+/// Parse the periods of the Pomodoro timer given as WORK/BREAK or WORK/BREAK/LONG_BREAK,
+/// each a duration like 25m.
+pub fn parse_pomodoro(s: &str) -> Result<PomodoroCfg, Error> {
+    let periods = s
+        .split('/')
+        .map(|period| match parse_duration(period)? {
+            Duration::ZERO => Err(Error::Usage(format!(
+                "invalid Pomodoro period: '{}', it must be longer than zero",
+                period
+            ))),
+            duration => Ok(duration),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let (work, break_, long_break) = match periods[..] {
+        [work, break_] => (work, break_, None),
+        [work, break_, long_break] => (work, break_, Some(long_break)),
+        _ => {
+            return Err(Error::Usage(format!(
+                "invalid Pomodoro periods: '{}', expected WORK/BREAK or WORK/BREAK/LONG_BREAK",
+                s
+            )))
+        }
+    };
+    Ok(PomodoroCfg {
+        work,
+        break_,
+        long_break,
+        colours: true,
+        interval: false,
+        rounds: None,
+    })
+}
+
+/// Parse the time of each side of the chess clock, a duration like 5m optionally followed
+/// by the increment, like 5m+3s.
+fn parse_chess(s: &str) -> Result<ChessCfg, Error> {
+    let (time, increment) = s.split_once('+').unwrap_or((s, "0"));
+    match parse_duration(time)? {
+        Duration::ZERO => Err(Error::Usage(format!(
+            "invalid chess clock time: '{}', it must be longer than zero",
+            time
+        ))),
+        time => Ok(ChessCfg {
+            time,
+            increment: parse_duration(increment)?,
+        }),
+    }
+}
+
+/// Parse the work and rest periods of the interval timer given as WORK/REST, each a
+/// duration like 40s. The rest can be zero to go straight from one round to the next.
+fn parse_interval(s: &str) -> Result<PomodoroCfg, Error> {
+    let (work, rest) = s.split_once('/').ok_or_else(|| {
+        Error::Usage(format!(
+            "invalid interval: '{}', expected WORK/REST, e.g. 40s/20s",
+            s
+        ))
+    })?;
+    let work = match parse_duration(work)? {
+        Duration::ZERO => {
+            return Err(Error::Usage(format!(
+                "invalid interval: '{}', the work period must be longer than zero",
+                s
+            )))
+        }
+        work => work,
+    };
+    Ok(PomodoroCfg {
+        work,
+        break_: parse_duration(rest)?,
+        long_break: None,
+        colours: true,
+        interval: true,
+        rounds: None,
+    })
+}
+
+/// Parse a whole number of minutes greater than zero.
+fn parse_minutes(s: &str) -> Result<Duration, Error> {
+    match s.parse::<u64>() {
+        Ok(minutes) if minutes > 0 => Ok(Duration::from_secs(minutes * 60)),
+        _ => Err(Error::Usage(format!("invalid number of minutes: '{}'", s))),
+    }
+}
+
+/// Parse a duration in the form HH:MM:SS, MM:SS, or SS, or with units like 1h30m.
+fn parse_duration(s: &str) -> Result<Duration, Error> {
+    let invalid = || {
+        Error::Usage(format!(
+            "invalid duration: '{}', expected HH:MM:SS, MM:SS, SS, or a number of hours, minutes, and seconds like 1h30m",
+            s
+        ))
+    };
+    if s.ends_with(|ch: char| ch.is_ascii_alphabetic()) {
+        return parse_units(s).map(Duration::from_secs).ok_or_else(invalid);
+    }
+    let parts = s
+        .split(':')
+        .map(|part| part.parse::<u64>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (hours, minutes, seconds) = match parts[..] {
+        [seconds] => return Ok(Duration::from_secs(seconds)),
+        [minutes, seconds] => (0, minutes, seconds),
+        [hours, minutes, seconds] if minutes < 60 => (hours, minutes, seconds),
+        _ => return Err(invalid()),
+    };
+    if seconds >= 60 {
+        return Err(invalid());
+    }
+    hours
+        .checked_mul(3600)
+        .and_then(|secs| secs.checked_add(minutes * 60 + seconds))
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+/// Parse a duration made of numbers followed by `h`, `m`, or `s`, e.g. 1h30m, into a
+/// number of seconds. Each unit can only be given once, in that order.
+fn parse_units(s: &str) -> Option<u64> {
+    let mut secs = 0u64;
+    let mut units = ["h", "m", "s"].iter().zip([3600, 60, 1]);
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest.find(|ch: char| !ch.is_ascii_digit())?;
+        let (number, after) = rest.split_at(digits);
+        let number = number.parse::<u64>().ok()?;
+        let (unit, scale) = units.find(|(unit, _)| after.starts_with(**unit))?;
+        secs = secs.checked_add(number.checked_mul(scale)?)?;
+        rest = &after[unit.len()..];
+    }
+    Some(secs)
+}
+
+/// Parse the argument to `--exit-at` or `--alarm`, a time of day as HH:MM or HH:MM:SS,
+/// optionally followed by am or pm.
+fn parse_time(s: &str) -> Result<Time, Error> {
+    let invalid = || {
+        Error::Usage(format!(
+            "invalid time: '{}', expected HH:MM or HH:MM:SS in 24-hour time, or either followed by am or pm, e.g. 7:30pm",
+            s
+        ))
+    };
+    let lower = s.to_ascii_lowercase();
+    let (time, pm) = match (lower.strip_suffix("am"), lower.strip_suffix("pm")) {
+        (Some(time), _) => (time.trim_end(), Some(false)),
+        (_, Some(time)) => (time.trim_end(), Some(true)),
+        _ => (lower.as_str(), None),
+    };
+    let parts = time
+        .split(':')
+        .map(|part| part.parse::<u8>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (hour, minute, second) = match parts[..] {
+        [hour, minute] => (hour, minute, 0),
+        [hour, minute, second] => (hour, minute, second),
+        _ => return Err(invalid()),
+    };
+    let hour = match pm {
+        Some(_) if !(1..=12).contains(&hour) => return Err(invalid()),
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    Time::from_hms(hour, minute, second).map_err(|_| invalid())
+}
+
+/// Parse the argument of `--since` or `--until`, either a date, which is taken to be midnight, a date
+/// and time in the clock's time zone, or an RFC 3339 timestamp.
+fn parse_moment(s: &str) -> Result<Moment, Error> {
+    if let Ok(moment) = OffsetDateTime::parse(s, &Rfc3339) {
+        return Ok(Moment::Offset(moment));
+    }
+    let date_time = format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]");
+    if let Ok(moment) = PrimitiveDateTime::parse(s, date_time) {
+        return Ok(Moment::Local(moment));
+    }
+    let date = format_description!("[year]-[month]-[day]");
+    match Date::parse(s, date) {
+        Ok(date) => Ok(Moment::Local(date.midnight())),
+        Err(_) => Err(Error::Usage(format!(
+            "invalid date: '{}', expected YYYY-MM-DD, YYYY-MM-DDTHH:MM:SS, or an RFC 3339 timestamp like 2024-01-01T09:00:00+10:00",
+            s
+        ))),
+    }
+}
+
+pub fn parse_args() -> Result<Options, Error> {
+    parse(std::env::args().skip(1), |options| {
+        apply_env_overrides(options)?;
+        load_config()?.apply(options);
+        Ok(())
+    })
+}
+
+/// Parse the command line arguments in `args`, then call `defaults` to fill in the
+/// options that weren't given.
+fn parse(
+    args: impl IntoIterator<Item = String>,
+    defaults: impl FnOnce(&mut Options) -> Result<(), Error>,
+) -> Result<Options, Error> {
+    // Options given on the command line override those in the environment, which
+    // override those in the configuration file. The environment and configuration
+    // file are read after the arguments so that bad values don't get in the way
+    // of --help
+    let mut options = Options::default();
+    let mut args = args.into_iter().peekable();
+    // The options that chose the font and mode, so that conflicts can be reported
+    let mut font_option: Option<(String, Font)> = None;
+    let mut mode_option: Option<(String, usize)> = None;
+    let (mut dim_from, mut dim_until) = (None, None);
+    let mut rounds = None;
+    let mut colour_given = false;
+    let mut force_colour = false;
+    let mut theme = None;
+    let mut truecolor = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                usage();
+                return Err(Error::ExitCode(ExitCode::SUCCESS));
+            }
+            "-V" | "--version" => {
+                println!("{}", version_string());
+                return Err(Error::ExitCode(ExitCode::SUCCESS));
+            }
+            "--version-json" => {
+                println!("{}", version_json());
+                return Err(Error::ExitCode(ExitCode::SUCCESS));
+            }
+            "-24" => options.twenty_four_hour = true,
+            "--pad-hour" => options.pad_hour = true,
+            "--no-period" => options.no_period = true,
+            "--lowercase-period" => options.lowercase_period = true,
+            "--dim-period" => options.period_style.dim = true,
+            "--period-color" | "--period-colour" => {
+                options.period_style.colour =
+                    Some(parse_colour(&args.next().ok_or_else(|| {
+                        Error::Usage("--period-colour requires an argument".into())
+                    })?)?);
+            }
+            "-c" | "--color" | "--colour" => {
+                colour_given = true;
+                let colours = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--colour requires an argument".into()))?
+                    .split(',')
+                    .map(parse_colour)
+                    .collect::<Result<Vec<_>, _>>()?;
+                options.colour = colours.first().copied();
+                if colours.len() > 1 {
+                    options.colours = colours;
+                }
+            }
+            "--gradient" => {
+                let missing = || Error::Usage("--gradient requires two colours".into());
+                let from = args.next().ok_or_else(missing)?;
+                options.gradient = Some(match from.split_once("..") {
+                    _ if from == "rainbow" => Gradient::Rainbow,
+                    Some((from, to)) => Gradient::Between(parse_colour(from)?, parse_colour(to)?),
+                    None => {
+                        let to = args.next().ok_or_else(missing)?;
+                        Gradient::Between(parse_colour(&from)?, parse_colour(&to)?)
+                    }
+                });
+            }
+            "--rainbow" => options.rainbow = true,
+            "--color-by-hour" | "--colour-by-hour" => options.colour_by_hour = true,
+            "--large" => options.font = Font::Large,
+            "--big" => options.font = Font::Big,
+            "--ascii" => options.font = Font::Ascii,
+            "--braille" => options.font = Font::Braille,
+            "--plain" | "--no-segments" => options.font = Font::Plain,
+            "--font" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--font requires an argument".into()))?;
+                options.font = parse_font(&name)?;
+            }
+            "--scale" => {
+                let scale = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--scale requires an argument".into()))?;
+                options.scale = Some(
+                    scale
+                        .parse()
+                        .ok()
+                        .filter(|&scale| scale > 0)
+                        .ok_or_else(|| Error::Usage(format!("invalid scale: '{}'", scale)))?,
+                );
+            }
+            "--binary" => {
+                // Keep the legend if --binary-legend came first
+                if !matches!(options.font, Font::Binary { .. }) {
+                    options.font = Font::Binary { legend: false };
+                }
+            }
+            "--show-off-segments" => options.show_off_segments = true,
+            "--binary-legend" => options.font = Font::Binary { legend: true },
+            "--cycle-color" | "--cycle-colour" => {
+                let period = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--cycle-colour requires an argument".into()))?;
+                let period = match parse_duration(&period)? {
+                    Duration::ZERO => {
+                        return Err(Error::Usage(format!(
+                            "invalid period: '{}', it must be longer than zero",
+                            period
+                        )))
+                    }
+                    period => period,
+                };
+                options.rainbow = true;
+                options.cycle_colour = true;
+                options.cycle_speed = Some(period.as_secs_f64());
+            }
+            "--cycle-speed" => {
+                let speed = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--cycle-speed requires an argument".into()))?;
+                options.cycle_speed = Some(
+                    speed
+                        .parse()
+                        .ok()
+                        .filter(|&speed: &f64| speed > 0. && speed.is_finite())
+                        .ok_or_else(|| Error::Usage(format!("invalid cycle speed: '{}'", speed)))?,
+                );
+            }
+            "--bold" => options.styles.bold = true,
+            "--dim" => options.styles.dim = true,
+            "--italic" => options.styles.italic = true,
+            "--underline" => options.styles.underline = true,
+            "--night-dim" => {
+                let mut time = || {
+                    args.next()
+                        .ok_or_else(|| Error::Usage("--night-dim requires two arguments".into()))
+                };
+                let (start, end) = (parse_time(&time()?)?, parse_time(&time()?)?);
+                options.night = Some((start, end));
+            }
+            "--dim-from" | "--dim-until" => {
+                let time = args
+                    .next()
+                    .ok_or_else(|| Error::Usage(format!("{} requires an argument", arg)))?;
+                match arg.as_str() {
+                    "--dim-from" => dim_from = Some(parse_time(&time)?),
+                    _ => dim_until = Some(parse_time(&time)?),
+                }
+            }
+            "--night-color" | "--night-colour" => {
+                options.night_colour =
+                    Some(parse_colour(&args.next().ok_or_else(|| {
+                        Error::Usage("--night-colour requires an argument".into())
+                    })?)?);
+            }
+            "--theme" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--theme requires an argument".into()))?;
+                if name == "list" {
+                    print_themes()?;
+                    return Err(Error::ExitCode(ExitCode::SUCCESS));
+                }
+                theme = Some(parse_theme(&name)?);
+            }
+            "--bg" | "--background" | "--background-color" | "--background-colour" => {
+                options.bg_colour = Some(parse_colour(&args.next().ok_or_else(|| {
+                    Error::Usage("--background-colour requires an argument".into())
+                })?)?);
+            }
+            "--title" | "--label" => {
+                options.title = Some(
+                    args.next()
+                        .ok_or_else(|| Error::Usage(format!("{} requires an argument", arg)))?,
+                );
+            }
+            "--title-position" | "--label-position" => {
+                let position = args
+                    .next()
+                    .ok_or_else(|| Error::Usage(format!("{} requires an argument", arg)))?;
+                options.title_side = match position.as_str() {
+                    "top" => Side::Before,
+                    "bottom" => Side::After,
+                    _ => {
+                        return Err(Error::Usage(format!(
+                            "invalid position: '{}', expected top or bottom",
+                            position
+                        )))
+                    }
+                };
+            }
+            "--title-color" | "--title-colour" => {
+                options.title_colour =
+                    Some(parse_colour(&args.next().ok_or_else(|| {
+                        Error::Usage("--title-colour requires an argument".into())
+                    })?)?);
+            }
+            "--force-color" | "--force-colour" => force_colour = true,
+            "--truecolor" => truecolor = Some(true),
+            "--no-truecolor" => truecolor = Some(false),
+            "--seconds-color" | "--seconds-colour" => {
+                options.seconds_colour = Some(parse_colour(&args.next().ok_or_else(|| {
+                    Error::Usage("--seconds-colour requires an argument".into())
+                })?)?);
+            }
+            "--border" => options.border = true,
+            "--border-color" | "--border-colour" => {
+                options.border_colour = Some(parse_colour(&args.next().ok_or_else(|| {
+                    Error::Usage("--border-colour requires an argument".into())
+                })?)?);
+            }
+            "--seconds" => options.show_seconds = true,
+            "--tenths" => options.show_tenths = true,
+            "--seconds-bar" => options.seconds_bar = true,
+            "--progress-bar" => options.progress_bar = true,
+            "--seconds-ring" => options.seconds_ring = true,
+            "--hour-progress" => options.hour_progress = true,
+            "--date" => options.show_date = true,
+            "--day-of-year" => options.day_of_year = true,
+            "--week" => options.week = true,
+            "--weekday" | "--weekday=after" => options.weekday = Some(Side::After),
+            "--weekday=before" => options.weekday = Some(Side::Before),
+            "--blink" => options.blink = Some(Blink::HalfSecond),
+            "--blink-separator" => options.blink = Some(Blink::OddSeconds),
+            "--vertical" => options.vertical = true,
+            "--separator" => {
+                let separator = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--separator requires an argument".into()))?;
+                options.separator = Some(parse_separator(&separator)?);
+            }
+            "-tz" | "--timezone" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--timezone requires an argument".into()))?;
+                options.timezone = Some(parse_timezone(&name)?);
+            }
+            "--countdown" | "--timer" => {
+                let duration = args
+                    .next()
+                    .ok_or_else(|| Error::Usage(format!("{} requires an argument", arg)))?;
+                options.mode = Mode::Countdown(parse_duration(&duration)?);
+            }
+            "-s" | "--stopwatch" => options.mode = Mode::Stopwatch,
+            "--pomodoro" => {
+                let work = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--pomodoro requires an argument".into()))?;
+                let cfg = if work.contains('/') {
+                    parse_pomodoro(&work)?
+                } else {
+                    let break_ = args.next_if(|arg| !arg.starts_with('-'));
+                    PomodoroCfg {
+                        work: parse_minutes(&work)?,
+                        break_: break_.as_deref().map_or(Ok(DEFAULT_BREAK), parse_minutes)?,
+                        long_break: None,
+                        colours: true,
+                        interval: false,
+                        rounds: None,
+                    }
+                };
+                options.mode = Mode::Pomodoro(cfg);
+            }
+            "--interval" => {
+                let periods = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--interval requires an argument".into()))?;
+                options.mode = Mode::Pomodoro(parse_interval(&periods)?);
+            }
+            "--rounds" => {
+                rounds = match args.next().map(|rounds| rounds.parse::<u32>()) {
+                    Some(Ok(rounds)) if rounds > 0 => Some(rounds),
+                    _ => {
+                        return Err(Error::Usage(
+                            "--rounds requires a number of rounds greater than zero".into(),
+                        ))
+                    }
+                }
+            }
+            "--chess" => {
+                let time = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--chess requires an argument".into()))?;
+                options.mode = Mode::Chess(parse_chess(&time)?);
+            }
+            "--uptime" => options.mode = Mode::Uptime(boot_time()),
+            "--since" => {
+                let moment = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--since requires an argument".into()))?;
+                options.mode = Mode::Since(parse_moment(&moment)?);
+            }
+            "--until" => {
+                let moment = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--until requires an argument".into()))?;
+                options.mode = Mode::Until(parse_moment(&moment)?);
+            }
+            "--overtime" => options.overtime = true,
+            "--epoch" | "--unix" => options.mode = Mode::Epoch,
+            "--hex" => options.mode = Mode::Hex,
+            "--analog" => options.mode = Mode::Analog,
+            "--row" => options.row = parse_anchor("--row", args.next())?,
+            "--col" => options.col = parse_anchor("--col", args.next())?,
+            "--zone" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--zone requires an argument".into()))?;
+                options.world.push(parse_timezone(&name)?);
+            }
+            "--world" => {
+                while let Some(name) = args.next_if(|arg| !arg.starts_with('-')) {
+                    options.world.push(parse_timezone(&name)?);
+                }
+                if options.world.is_empty() {
+                    return Err(Error::Usage(
+                        "--world requires at least one time zone".into(),
+                    ));
+                }
+            }
+            "--utc" => options.utc = true,
+            "--offset" => {
+                let offset = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--offset requires an argument".into()))?;
+                options.offset = Some(parse_offset(&offset)?);
+            }
+            "--no-utc-suffix" => options.no_utc_suffix = true,
+            "--no-alternate-screen" => options.no_alternate_screen = true,
+            "--date-format" => {
+                options.date_format =
+                    Some(args.next().ok_or_else(|| {
+                        Error::Usage("--date-format requires an argument".into())
+                    })?);
+            }
+            "--iso" => options.iso = true,
+            "--chime-hourly" => options.chime_hourly = true,
+            "--flash-on-minute" => options.flash_on_minute = true,
+            "--screensaver" => options.screensaver = true,
+            "--jitter" => options.jitter = true,
+            "--no-bell" => options.no_bell = true,
+            "--hold" => options.hold = true,
+            "--exit-at" => {
+                let time = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--exit-at requires an argument".into()))?;
+                options.exit_at = Some(parse_time(&time)?);
+            }
+            "--alarm" => {
+                let time = args
+                    .next()
+                    .ok_or_else(|| Error::Usage("--alarm requires an argument".into()))?;
+                options.alarms.push(parse_time(&time)?);
+            }
+            "--on-finish" => {
+                options.on_finish = Some(
+                    args.next()
+                        .ok_or_else(|| Error::Usage("--on-finish requires an argument".into()))?,
+                );
+            }
+            "--print" => options.print = true,
+            "--json" => options.json = true,
+            "--debug" => options.debug = true,
+            "--benchmark" => options.benchmark = true,
+            "--format" => {
+                options.format = Some(
+                    args.next()
+                        .ok_or_else(|| Error::Usage("--format requires an argument".into()))?,
+                );
+            }
+            otherwise => return Err(Error::Usage(format!("unknown option: '{}'", otherwise))),
+        }
+        if FONT_OPTIONS.contains(&arg.as_str()) {
+            if let Some((previous, font)) = &font_option {
+                if mem::discriminant(font) != mem::discriminant(&options.font) {
+                    return Err(Error::Usage(format!(
+                        "{} can't be used with {}",
+                        arg, previous
+                    )));
+                }
+            }
+            font_option = Some((arg, options.font));
+        } else if let Some(mode) = MODE_OPTIONS
+            .iter()
+            .position(|names| names.contains(&arg.as_str()))
+        {
+            if let Some((previous, previous_mode)) = &mode_option {
+                if *previous_mode != mode {
+                    return Err(Error::Usage(format!(
+                        "{} can't be used with {}",
+                        arg, previous
+                    )));
+                }
+            }
+            mode_option = Some((arg, mode));
+        }
+    }
+    defaults(&mut options)?;
+    if colour_given && options.gradient.is_some() {
+        return Err(Error::Usage(
+            "--gradient can't be used with --colour, the gradient sets the colour".into(),
+        ));
+    }
+    match &mut options.mode {
+        Mode::Pomodoro(cfg) if cfg.interval => cfg.rounds = rounds,
+        _ if rounds.is_some() => {
+            return Err(Error::Usage(
+                "--rounds can only be used with --interval".into(),
+            ))
+        }
+        _ => {}
+    }
+    match (dim_from, dim_until) {
+        (Some(start), Some(end)) => options.night = Some((start, end)),
+        (None, None) => {}
+        _ => {
+            return Err(Error::Usage(
+                "--dim-from and --dim-until must be given together".into(),
+            ))
+        }
+    }
+
+    if options.utc && options.timezone.is_some() {
+        return Err(Error::Usage("--utc can't be used with --timezone".into()));
+    }
+    if options.offset.is_some() && (options.utc || options.timezone.is_some()) {
+        return Err(Error::Usage(
+            "--offset can't be used with --utc or --timezone".into(),
+        ));
+    }
+    if !options.world.is_empty()
+        && (options.utc || options.timezone.is_some() || options.offset.is_some())
+    {
+        return Err(Error::Usage(
+            "--world and --zone can't be used with --utc, --timezone, or --offset".into(),
+        ));
+    }
+    // A single zone is shown just like --timezone
+    if options.world.len() == 1 {
+        options.timezone = options.world.pop();
+    }
+    if options.timezone.is_none()
+        && !options.utc
+        && options.offset.is_none()
+        && options.world.is_empty()
+        && UtcOffset::current_local_offset().is_err()
+    {
+        eprintln!("warning: the local time zone can't be determined, showing UTC");
+    }
+    if options.no_period && options.twenty_four_hour {
+        return Err(Error::Usage("--no-period can't be used with -24".into()));
+    }
+    if options.period_style.colour.is_some() && options.twenty_four_hour {
+        return Err(Error::Usage(
+            "--period-colour can't be used with -24, there's no AM/PM to colour".into(),
+        ));
+    }
+    if options.format.is_some() {
+        if options.twenty_four_hour {
+            return Err(Error::Usage("-24 can't be used with --format".into()));
+        }
+        if options.pad_hour {
+            return Err(Error::Usage(
+                "--pad-hour can't be used with --format".into(),
+            ));
+        }
+        if options.show_seconds {
+            return Err(Error::Usage("--seconds can't be used with --format".into()));
+        }
+        if options.show_tenths {
+            return Err(Error::Usage("--tenths can't be used with --format".into()));
+        }
+    }
+    if let Mode::Analog = options.mode {
+        if font_option.is_some() || options.scale.is_some() {
+            return Err(Error::Usage(
+                "--analog can't be used with a font, including --binary and --large, or --scale"
+                    .into(),
+            ));
+        }
+        if options.format.is_some() || options.iso || options.show_tenths {
+            return Err(Error::Usage(
+                "--analog can't be used with --format, --iso, or --tenths".into(),
+            ));
+        }
+        if options.show_date
+            || options.week
+            || options.day_of_year
+            || options.seconds_bar
+            || options.progress_bar
+            || options.hour_progress
+        {
+            return Err(Error::Usage(
+                "--analog can't be used with --date, --week, --day-of-year, or a progress bar"
+                    .into(),
+            ));
+        }
+        if !options.world.is_empty() || options.print || options.json {
+            return Err(Error::Usage(
+                "--analog can't be used with --world, --print, or --json".into(),
+            ));
+        }
+        if options.border {
+            return Err(Error::Usage("--analog can't be used with --border".into()));
+        }
+    }
+    if options.iso {
+        if options.format.is_some() {
+            return Err(Error::Usage("--iso can't be used with --format".into()));
+        }
+        if matches!(options.mode, Mode::Epoch | Mode::Hex) {
+            return Err(Error::Usage(
+                "--iso can't be used with --epoch or --hex".into(),
+            ));
+        }
+        options.format = Some(ISO_FORMAT.to_string());
+    }
+    if options.colour_by_hour && options.rainbow {
+        return Err(Error::Usage(
+            "--colour-by-hour can't be used with --rainbow".into(),
+        ));
+    }
+    if let Mode::Epoch = options.mode {
+        // These only change how the time of day is shown, so they're harmless
+        if options.twenty_four_hour {
+            eprintln!("warning: -24 has no effect with --epoch or --unix, ignoring it");
+            options.twenty_four_hour = false;
+        }
+        if options.show_seconds || options.show_tenths {
+            eprintln!("warning: --seconds and --tenths have no effect with --epoch or --unix, ignoring them");
+            options.show_seconds = false;
+            options.show_tenths = false;
+        }
+        if options.format.is_some() {
+            return Err(Error::Usage("--format can't be used with --epoch".into()));
+        }
+    }
+    if let Mode::Hex = options.mode {
+        if options.show_tenths {
+            return Err(Error::Usage("--tenths can't be used with --hex".into()));
+        }
+        if options.format.is_some() {
+            return Err(Error::Usage("--format can't be used with --hex".into()));
+        }
+        // Binary digits only go up to 9
+        if matches!(options.font, Font::Binary { .. }) {
+            return Err(Error::Usage("--binary can't be used with --hex".into()));
+        }
+        options.show_seconds = true;
+    }
+    // The binary clock shows the hours, minutes, and seconds of the 24-hour time
+    if matches!(options.font, Font::Binary { .. }) && options.format.is_none() {
+        options.twenty_four_hour = true;
+        options.show_seconds = true;
+    }
+    if options.seconds_colour.is_some() && !(options.show_seconds || options.show_tenths) {
+        eprintln!("warning: --seconds-colour has no effect without --seconds, ignoring it");
+        options.seconds_colour = None;
+    }
+    if options.seconds_bar {
+        if options.show_seconds || options.show_tenths {
+            return Err(Error::Usage(
+                "--seconds-bar can't be used with --seconds or --tenths".into(),
+            ));
+        }
+        if !options.world.is_empty() {
+            return Err(Error::Usage(
+                "--seconds-bar can't be used with --world".into(),
+            ));
+        }
+    }
+    if options.screensaver || options.jitter {
+        if options.screensaver && options.jitter {
+            return Err(Error::Usage(
+                "--jitter can't be used with --screensaver".into(),
+            ));
+        }
+        if !matches!((options.row, options.col), (Anchor::Center, Anchor::Center)) {
+            return Err(Error::Usage(
+                "--screensaver and --jitter can't be used with --row or --col".into(),
+            ));
+        }
+        if matches!(options.mode, Mode::Analog | Mode::Chess(_)) {
+            return Err(Error::Usage(
+                "--screensaver and --jitter can't be used with --analog or --chess".into(),
+            ));
+        }
+    }
+    if options.on_finish.is_some()
+        && options.alarms.is_empty()
+        && !matches!(options.mode, Mode::Countdown(_) | Mode::Until(_))
+    {
+        return Err(Error::Usage(
+            "--on-finish can only be used with --countdown, --until, or --alarm".into(),
+        ));
+    }
+    if options.seconds_ring {
+        if !matches!(options.mode, Mode::Clock) {
+            return Err(Error::Usage(
+                "--seconds-ring can only be used with the time of day".into(),
+            ));
+        }
+        if options.screensaver
+            || options.jitter
+            || !matches!((options.row, options.col), (Anchor::Center, Anchor::Center))
+        {
+            return Err(Error::Usage(
+                "--seconds-ring can't be used with --row, --col, --screensaver, or --jitter".into(),
+            ));
+        }
+    }
+    if options.hour_progress && !options.world.is_empty() {
+        return Err(Error::Usage(
+            "--hour-progress can't be used with --world".into(),
+        ));
+    }
+    if options.progress_bar {
+        if options.seconds_bar {
+            return Err(Error::Usage(
+                "--progress-bar can't be used with --seconds-bar".into(),
+            ));
+        }
+        if !options.world.is_empty() {
+            return Err(Error::Usage(
+                "--progress-bar can't be used with --world".into(),
+            ));
+        }
+    }
+    match options.mode {
+        Mode::Since(moment) if options.moment(moment) > options.now() => {
+            return Err(Error::Usage(
+                "the time given to --since is in the future, use --until to count down to it"
+                    .into(),
+            ));
+        }
+        Mode::Until(moment) if options.moment(moment) <= options.now() => {
+            return Err(Error::Usage(
+                "the time given to --until has passed, use --since to count up from it".into(),
+            ));
+        }
+        Mode::Until(_) => {}
+        _ if options.overtime => {
+            return Err(Error::Usage(
+                "--overtime can only be used with --until".into(),
+            ));
+        }
+        _ => {}
+    }
+    if options.benchmark {
+        if options.print || options.json {
+            return Err(Error::Usage(
+                "--benchmark can't be used with --print or --json".into(),
+            ));
+        }
+        if !matches!(options.mode, Mode::Clock) || !options.world.is_empty() {
+            return Err(Error::Usage(
+                "--benchmark can only be used with the time of day".into(),
+            ));
+        }
+    }
+    if options.print && options.json {
+        return Err(Error::Usage("--print can't be used with --json".into()));
+    }
+    if options.vertical {
+        if !matches!(options.mode, Mode::Clock) {
+            return Err(Error::Usage(
+                "--vertical can only be used when showing the time of day".into(),
+            ));
+        }
+        if !options.world.is_empty() || options.print || options.json {
+            return Err(Error::Usage(
+                "--vertical can't be used with --world, --print, or --json".into(),
+            ));
+        }
+    }
+    if options.json && options.title.is_some() {
+        return Err(Error::Usage("--title can't be used with --json".into()));
+    }
+    if options.border && (options.print || options.json) {
+        return Err(Error::Usage(
+            "--border can't be used with --print or --json".into(),
+        ));
+    }
+    for (used, option) in [(options.print, "--print"), (options.json, "--json")] {
+        if !used {
+            continue;
+        }
+        if matches!(
+            options.mode,
+            Mode::Countdown(_) | Mode::Stopwatch | Mode::Pomodoro(_) | Mode::Chess(_)
+        ) {
+            return Err(Error::Usage(format!(
+                "{} can't be used with --countdown, --stopwatch, --pomodoro, or --chess",
+                option
+            )));
+        }
+        if !options.world.is_empty() {
+            return Err(Error::Usage(format!(
+                "{} can't be used with --world",
+                option
+            )));
+        }
+    }
+    // The theme only fills in the colours that weren't chosen on the command line,
+    // in the environment, or in the configuration file
+    let colour_chosen = options.colour.is_some();
+    if let Some(theme) = theme {
+        options.colour = options.colour.or(Some(theme.colour));
+        options.bg_colour = options.bg_colour.or(Some(theme.background));
+        if options.show_seconds || options.show_tenths {
+            options.seconds_colour = options.seconds_colour.or(Some(theme.accent));
+        }
+        if options.show_period() {
+            options.period_style.colour = options.period_style.colour.or(Some(theme.accent));
+        }
+    }
+    if options.colour_by_hour && colour_chosen {
+        eprintln!("warning: --colour-by-hour overrides --colour");
+    }
+    if options.colour.is_some() && options.colour == options.bg_colour {
+        eprintln!(
+            "warning: the colour and background colour are the same, the clock won't be visible"
+        );
+    }
+    apply_no_color(
+        &mut options,
+        std::env::var_os("NO_COLOR").as_deref(),
+        force_colour,
+    );
+    options.truecolor = truecolor.unwrap_or_else(supports_rgb);
+    // The JSON output is for status bars rather than the terminal
+    if !options.truecolor && !options.json {
+        options.downsample_colours();
+    }
+    // Check the formats are valid before the clock starts
+    options.format()?;
+    options.date_format()?;
+
+    Ok(options)
+}
+
+/// Whether the colours chosen are used, given the value of `NO_COLOR` and whether
+/// `--force-colour` was given. A non-empty NO_COLOR takes precedence over any colours
+/// chosen unless they're forced, see https://no-color.org/
+pub fn use_colour(no_color: Option<&OsStr>, force_colour: bool) -> bool {
+    force_colour || no_color.map_or(true, OsStr::is_empty)
+}
+
+/// Remove the colours chosen if they aren't used, see [use_colour].
+fn apply_no_color(options: &mut Options, no_color: Option<&OsStr>, force_colour: bool) {
+    if !use_colour(no_color, force_colour) {
+        options.remove_colours();
+    }
+}
+
+/// Set the options that can be given as environment variables and weren't given on
+/// the command line.
+fn apply_env_overrides(options: &mut Options) -> Result<(), Error> {
+    if options.colour.is_some() {
+        return Ok(());
+    }
+    if let Some(colour) = std::env::var_os("CLOCK_COLOR").filter(|value| !value.is_empty()) {
+        let colour = colour.to_string_lossy();
+        options.colour = Some(parse_colour(&colour).map_err(|err| match err {
+            Error::Message(message) => Error::Message(format!("CLOCK_COLOR: {}", message)),
+            err => err,
+        })?);
+    }
+    Ok(())
+}
+
+/// The options that choose the font, only one font can be chosen.
+const FONT_OPTIONS: [&str; 9] = [
+    "--font",
+    "--plain",
+    "--no-segments",
+    "--large",
+    "--big",
+    "--ascii",
+    "--braille",
+    "--binary",
+    "--binary-legend",
+];
+
+/// The options that choose the mode, with their aliases, only one mode can be chosen.
+const MODE_OPTIONS: [&[&str]; 11] = [
+    &["--countdown", "--timer"],
+    &["-s", "--stopwatch"],
+    &["--pomodoro"],
+    &["--interval"],
+    &["--chess"],
+    &["--uptime"],
+    &["--since"],
+    &["--until"],
+    &["--epoch", "--unix"],
+    &["--hex"],
+    &["--analog"],
+];
+
+/// The names accepted by `--font`.
+const FONT_NAMES: [&str; 7] = [
+    "segment", "plain", "large", "big", "ascii", "braille", "binary",
+];
+
+fn parse_font(name: &str) -> Result<Font, Error> {
+    match name {
+        "segment" => Ok(Font::Segment),
+        "plain" => Ok(Font::Plain),
+        "large" => Ok(Font::Large),
+        "big" => Ok(Font::Big),
+        "ascii" => Ok(Font::Ascii),
+        "braille" => Ok(Font::Braille),
+        "binary" => Ok(Font::Binary { legend: false }),
+        _ => Err(Error::Usage(format!(
+            "unknown font: '{}', expected one of {}",
+            name,
+            FONT_NAMES.join(", ")
+        ))),
+    }
+}
+
+/// Parse the argument to `--row` or `--col`, negative numbers count from the end.
+fn parse_anchor(option: &str, arg: Option<String>) -> Result<Anchor, Error> {
+    let arg = arg.ok_or_else(|| Error::Usage(format!("{} requires an argument", option)))?;
+    let invalid = || Error::Usage(format!("invalid {} '{}', expected a number", option, arg));
+    match arg.strip_prefix('-') {
+        Some(offset) => {
+            let offset = offset.parse::<u16>().map_err(|_| invalid())?;
+            Ok(Anchor::RelativeFromEnd(
+                offset.checked_sub(1).ok_or_else(invalid)?,
+            ))
+        }
+        None => arg.parse().map(Anchor::Absolute).map_err(|_| invalid()),
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<TimeZone, Error> {
+    TimeZone::named(name).map_err(|err| match err {
+        TzError::InvalidName(_) => Error::Usage(format!(
+            "{}, expected a name from the IANA time zone database \
+            in the form Area/Location, e.g. Europe/Berlin or America/New_York",
+            err
+        )),
+        _ => Error::Message(err.to_string()),
+    })
+}
+
+pub fn parse_colour(s: &str) -> Result<Color, Error> {
+    if let Some(hex) = s.strip_prefix('#') {
+        parse_hex(hex)
+    } else if let Some(index) = s.strip_prefix("ansi:") {
+        parse_ansi(index)
+    } else if s.bytes().all(|b| b.is_ascii_digit()) {
+        parse_ansi(s)
+    } else {
+        Color::try_from(s).map_err(|()| Error::Message(format!("unable to parse colour: '{}'", s)))
+    }
+}
+
+fn parse_theme(name: &str) -> Result<&'static Theme, Error> {
+    THEMES
+        .iter()
+        .find(|theme| theme.name == name)
+        .ok_or_else(|| {
+            let names = THEMES.iter().map(|theme| theme.name).collect::<Vec<_>>();
+            Error::Usage(format!(
+                "unknown theme: '{}', expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+
+/// Print the name of each theme next to a sample of its colours, for `--theme list`.
+fn print_themes() -> Result<(), Error> {
+    let fit = |colour| {
+        if supports_rgb() {
+            colour
+        } else {
+            to_ansi(colour)
+        }
+    };
+    let mut stdout = stdout();
+    for theme in &THEMES {
+        execute!(
+            stdout,
+            Print(format!("{:<10} ", theme.name)),
+            SetBackgroundColor(fit(theme.background)),
+            SetForegroundColor(fit(theme.colour)),
+            Print(" 12:34"),
+            SetForegroundColor(fit(theme.accent)),
+            Print(":56 "),
+            SetForegroundColor(Color::Reset),
+            SetBackgroundColor(Color::Reset),
+            Print("\n")
+        )?;
+    }
+    Ok(())
+}
+
+/// Parse a fixed UTC offset given as +HH:MM or -HH:MM, up to 14 hours either way.
+fn parse_offset(s: &str) -> Result<UtcOffset, Error> {
+    let invalid = || {
+        Error::Usage(format!(
+            "invalid offset: '{}', expected +HH:MM or -HH:MM up to 14:00, e.g. +05:30",
+            s
+        ))
+    };
+    let (sign, rest) = match (s.get(..1), s.get(1..)) {
+        (Some("+"), Some(rest)) => (1, rest),
+        (Some("-"), Some(rest)) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let (hours, minutes) = rest.split_once(':').ok_or_else(invalid)?;
+    let two_digits = |part: &str| part.len() == 2 && part.bytes().all(|b| b.is_ascii_digit());
+    if !two_digits(hours) || !two_digits(minutes) {
+        return Err(invalid());
+    }
+    let hours = hours.parse::<i8>().map_err(|_| invalid())?;
+    let minutes = minutes.parse::<i8>().map_err(|_| invalid())?;
+    if !(0..=14).contains(&hours) || !(0..=59).contains(&minutes) || (hours == 14 && minutes > 0) {
+        return Err(invalid());
+    }
+    UtcOffset::from_hms(sign * hours, sign * minutes, 0).map_err(|_| invalid())
+}
+
+/// Parse an index into the 256 colour palette of the terminal.
+fn parse_ansi(index: &str) -> Result<Color, Error> {
+    index.parse::<u8>().map(Color::AnsiValue).map_err(|_| {
+        Error::Message(format!(
+            "invalid colour: '{}', palette colours are numbered 0 to 255",
+            index
+        ))
+    })
+}
+
+pub fn parse_hex(hex: &str) -> Result<Color, Error> {
+    // Checked first so that slicing by byte below can't split a character
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(Error::Message(format!("invalid colour: '#{}'", hex)));
+    }
+    let r = u8::from_str_radix(&hex[0..2], 16).ok();
+    let g = u8::from_str_radix(&hex[2..4], 16).ok();
+    let b = u8::from_str_radix(&hex[4..6], 16).ok();
+    match (r, g, b) {
+        (Some(r), Some(g), Some(b)) => Ok(Color::from((r, g, b))),
+        _ => Err(Error::Message(format!("invalid colour: '#{}'", hex))),
+    }
+}
+
+pub fn usage() {
+    eprintln!(
+        "{}
+
+{bin} displays a clock using seven-segment characters.
+
+USAGE:
+    {bin} [OPTIONS]
+
+OPTIONS:
+    -h, --help
+            Prints this help information.
+
+    -V, --version
+            Prints the version.
+
+    --version-json
+            Prints the name, version, rustc version, target, and profile of
+            the build as a JSON object.
+
+    -24
+            Use 24-hour time.
+
+    --pad-hour
+            Pad the hour with a leading zero in 12-hour time, e.g. 09:30 AM.
+
+    --no-period
+            Leave out AM/PM in 12-hour time.
+
+    --lowercase-period
+            Show am/pm instead of AM/PM in 12-hour time.
+
+    --dim-period
+            Draw AM/PM dimmed.
+
+    --period-color, --period-colour COLOUR
+            Set the colour of AM/PM. COLOUR is the same as for --colour.
+            Can't be combined with -24.
+
+    -c, --color, --colour COLOUR
+            Set the colour of the clock.
+            COLOUR can be an RGB hex colour (#RRGGBB), a colour from the 256
+            colour palette of the terminal by number (0 to 255, or ansi:N), or
+            one of the eight standard colour names: black, red, green, yellow,
+            blue, magenta, cyan, or white. With --world a comma separated list
+            of colours sets the colour of each time zone.
+
+    --gradient FROM TO, --gradient FROM..TO, --gradient rainbow
+            Fade the colour of the time from FROM on the left to TO on the
+            right. FROM and TO are the same as for --colour. With rainbow the
+            time goes through the colours of the rainbow from left to right.
+            Can't be combined with --colour.
+
+    --font FONT
+            Draw the digits with FONT, one of segment, plain, large, big,
+            ascii, braille, or binary. The default is segment, the seven-segment
+            characters. The other fonts are the same as the options below.
+
+    --plain, --no-segments
+            Draw ordinary digits instead of seven-segment characters.
+
+    --large
+            Draw the digits three rows tall with box-drawing characters.
+
+    --big
+            Draw the digits five rows tall with block characters.
+
+    --ascii
+            Draw the digits three rows tall with ASCII characters. Use this if
+            the clock shows boxes instead of digits because the terminal font
+            doesn't have the seven-segment characters.
+
+    --braille
+            Draw the digits as dot matrices with Braille characters, two rows
+            tall.
+
+    --scale N
+            Draw the clock N times bigger, each character becomes an N by N
+            block of itself. The scale is reduced if the terminal is resized
+            too small for it.
+
+    --binary
+            Show a binary clock. Each digit of the 24-hour time, including
+            seconds, is a column of dots for its bits, lit dots are drawn in
+            the colour of the clock.
+
+    --binary-legend
+            Show a binary clock with H, M, and S below the columns of the
+            hours, minutes, and seconds.
+
+    --show-off-segments
+            Draw the unlit segments of each digit in dark grey, like a real
+            seven-segment display. Only the --large, --big, and --ascii
+            digits have room for them.
+
+    --rainbow
+            Cycle the colour of the clock through the colours of the rainbow.
+
+    --cycle-color, --cycle-colour PERIOD
+            Slowly turn the hue of the colour of the clock around the colour
+            wheel, taking PERIOD to go all the way round, e.g. 10m. Without
+            --colour it goes through the colours of the rainbow. Only used when
+            the terminal supports RGB colours, see --truecolor, otherwise the
+            colour stays fixed.
+
+    --color-by-hour, --colour-by-hour
+            Change the colour of the clock through the day, from deep blue at
+            midnight through orange at dawn, yellow and white in the middle of
+            the day, and red at dusk. Overrides --colour.
+
+    --cycle-speed SECONDS
+            The number of seconds --rainbow takes to go through every colour.
+            The default is 10.
+
+    --theme THEME
+            Use the colours of THEME for the clock, the background, and the
+            seconds and AM/PM. THEME is one of amber, dracula, gruvbox, nord,
+            phosphor, or solarized, and --theme list shows a sample of each.
+            Colours chosen with the other options, CLOCK_COLOR, or the
+            configuration file take precedence.
+
+    --bg, --background, --background-colour COLOUR
+            Set the background colour. COLOUR is the same as for --colour.
+
+    --night-dim START END, --dim-from START --dim-until END
+            Draw the clock in a darker version of its colour, or dark grey if
+            it has none, from START until END, e.g. --night-dim 22:00 07:00.
+            The times are given the same way as for --alarm, and the clock is
+            never dimmed if they're the same.
+
+    --night-color, --night-colour COLOUR
+            Set the colour used by --night-dim instead. COLOUR is the same as
+            for --colour.
+
+    --bold, --dim, --italic, --underline
+            Draw the clock in bold, dimmed, italic, or underlined. They can be
+            combined, although most terminals don't show bold and dim together.
+
+    --title, --label TEXT
+            Show TEXT above the clock as it is, without seven-segment
+            characters. It's cut short with an ellipsis if it's wider than the
+            terminal.
+
+    --title-position, --label-position top|bottom
+            Show the title above or below the time. The default is top.
+
+    --title-color, --title-colour COLOUR
+            Set the colour of the title. COLOUR is the same as for --colour.
+
+    --truecolor, --no-truecolor
+            Draw RGB colours as they are, or as the nearest colour of the 256
+            colour palette. By default RGB colours are used when COLORTERM is
+            truecolor or 24bit, or TERM ends with -direct.
+
+    --force-color, --force-colour
+            Use the colours chosen even when NO_COLOR is set.
+
+    --seconds-color, --seconds-colour COLOUR
+            Draw the seconds in COLOUR, e.g. a dimmer colour to make them less
+            distracting. COLOUR is the same as for --colour. Only used with
+            --seconds or --tenths.
+
+    --border
+            Draw a box with rounded corners around the clock.
+
+    --border-color, --border-colour COLOUR
+            Set the colour of the border. COLOUR is the same as for --colour.
+
+    --seconds
+            Include seconds.
+
+    --tenths
+            Include seconds and tenths of a second.
+
+    --seconds-bar
+            Show a bar below the time that fills up over each minute instead
+            of the seconds.
+
+    --seconds-ring
+            Show a ring of dots around the clock that fills up a second at a
+            time over each minute. It's left out when the terminal is too
+            small to fit it around the clock.
+
+    --progress-bar
+            Show a bar across the terminal below the time that fills up a
+            second at a time over each minute.
+
+    --hour-progress
+            Show a bar below the time that fills up over each hour, a minute
+            at a time.
+
+    --date
+            Show the date below the time.
+
+    --date-format FORMAT
+            Use a custom format for the date shown by --date. FORMAT is the
+            same as for --format. The default is
+            '[weekday repr:short] [year]-[month]-[day]'.
+
+    --week
+            Show the ISO 8601 week number below the time, and the date if it's
+            shown, e.g. W20.
+
+    --day-of-year
+            Show the day of the year and the number of days in the year below
+            the time, date, and week number, e.g. 135/366.
+
+    --weekday, --weekday=before, --weekday=after
+            Show the abbreviated day of the week before or after the time.
+            The default is after.
+
+    --blink
+            Blink the colon once a second. Has no effect with --seconds.
+
+    --vertical
+            Stack the hours, minutes, and seconds one per line, for narrow
+            terminals. The time is shown on one line while the terminal is too
+            short for it.
+
+    --separator CHAR
+            Show CHAR in place of each colon of the time, e.g. '.' or ' '.
+
+    --blink-separator
+            Hide the colon on odd seconds.
+
+    -tz, --timezone ZONE
+            Show the time in ZONE instead of the local time zone.
+            ZONE is an IANA time zone name, e.g. America/New_York. Daylight
+            saving changes are followed while the clock is running. Zones are
+            read from the time zone database of Unix-like systems, elsewhere
+            only UTC is available.
+
+    --row N, --col N
+            Position the clock N rows from the top or N columns from the left
+            of the terminal instead of centring it. Negative numbers are
+            counted from the bottom or right, -1 is the last row or column.
+
+    --screensaver
+            Move the clock slowly around the terminal, bouncing off the edges.
+
+    --jitter
+            Move the clock up to two rows and columns from the centre at random
+            every minute, to avoid burn-in on OLED screens.
+
+    --world ZONE...
+            Show the time in each ZONE on its own line, labelled with the
+            zone name. ZONE is the same as for --timezone.
+
+    --zone ZONE
+            Add ZONE to the world clock. Can be repeated, a single --zone is
+            the same as --timezone.
+
+    --utc
+            Show the time in UTC, followed by UTC.
+
+    --no-utc-suffix
+            Don't show UTC after the time with --utc.
+
+    --offset OFFSET
+            Show the time at a fixed offset from UTC instead of the local time
+            zone. OFFSET is +HH:MM or -HH:MM, up to 14 hours, e.g. +05:30.
+
+    --format FORMAT
+            Use a custom time format. Can't be combined with -24, --pad-hour,
+            --no-period, --seconds, or --tenths.
+            FORMAT is a time crate format description, e.g.
+            '[hour]:[minute]:[second] [weekday repr:short]'. See
+            https://time-rs.github.io/book/api/format-description.html
+
+    --chime-hourly
+            Ring the terminal bell and flash the clock at the start of every
+            hour.
+
+    --flash-on-minute
+            Briefly flash the clock white at the start of every minute.
+
+    --alarm TIME
+            Ring the bell and flash the clock when the clock next reaches TIME
+            until a key is pressed. TIME is HH:MM or HH:MM:SS in 24-hour time,
+            or either followed by am or pm, e.g. 7:30pm. Can be repeated.
+
+    --no-bell
+            Don't ring the bell for --chime-hourly, --alarm, or the end of the
+            countdown, only flash the clock.
+
+    --on-finish COMMAND
+            Run COMMAND with the shell when the countdown finishes or an alarm
+            goes off, e.g. --on-finish 'paplay bell.oga'. Its output is
+            discarded, and the clock waits for it to finish on exit, reporting
+            it if it failed.
+
+    --exit-at TIME
+            Quit when the clock next reaches TIME, given the same way as for
+            --alarm.
+
+    --no-alternate-screen
+            Draw the clock on the main screen instead of the alternate screen,
+            so the last time shown is left behind on exit.
+
+    --print
+            Print the time and exit instead of running the clock, e.g. for use
+            in scripts.
+
+    --json
+            Print a JSON object with the time on its own line each time it
+            changes instead of running the clock. This is suitable for a
+            custom waybar module with return-type json.
+
+    --benchmark
+            Convert and render the time over and over for five seconds without
+            drawing it, then print how many times a second it managed, and the
+            mean, shortest, and longest time each took.
+
+    --debug
+            Show how long the last frame took to render, the longest, and the
+            mean in microseconds on the bottom row, updated every second.
+
+    --iso
+            Show the date and time in ISO 8601 (RFC 3339) format, e.g.
+            2024-05-14T09:30:15+10:00. Can't be combined with --format.
+
+    --countdown, --timer DURATION
+            Count down from DURATION instead of showing the time, then ring the
+            bell, flash, and exit. DURATION is HH:MM:SS, MM:SS, SS, or a number
+            of hours, minutes, and seconds like 10m or 1h30m.
+
+    --hold
+            Keep flashing when the countdown finishes until a key is pressed.
+
+    --epoch, --unix
+            Show the number of seconds since the Unix epoch instead of the time.
+            -24, --seconds, and --tenths are ignored.
+
+    --hex
+            Show the time with the 24-hour hours, minutes, and seconds each as
+            two hexadecimal digits, e.g. 0E:1E:2D for 14:30:45.
+
+    --analog
+            Show the time on an analog clock face filling the terminal. The
+            colour applies to the hands, and --seconds adds a second hand.
+            Can't be combined with a font, --hex, or --scale.
+
+    --uptime
+            Show how long the system has been up, e.g. 3 days 04:05:06. Where
+            the uptime can't be read, the time since the clock started is shown
+            instead.
+
+    --since DATE
+            Show the time elapsed since DATE, e.g. 12d 03:04:05. DATE is
+            YYYY-MM-DD for midnight, YYYY-MM-DDTHH:MM:SS in the clock's time
+            zone, or an RFC 3339 timestamp like 2024-01-01T09:00:00+10:00.
+
+    --until DATE
+            Count down to DATE, which is the same as for --since, e.g.
+            12d 03:04:05, then ring the bell, flash, and exit. The days are left
+            out on the last day.
+
+    --overtime
+            Keep going past the target of --until, counting up from it as a
+            negative time.
+
+    --pomodoro WORK [BREAK], --pomodoro WORK/BREAK[/LONG_BREAK]
+            Alternate between counting down WORK minutes of work in red and
+            BREAK minutes of break in green, ringing the bell at the end of
+            each. BREAK is 5 if it's not given. With a slash the periods are
+            durations like 25m/5m, and every fourth break is LONG_BREAK if it's
+            given. Press space to pause or resume and 'n' to skip to the next
+            period. The number of work periods finished is shown in the top
+            right corner.
+
+    --chess TIME[+INCREMENT]
+            Show a chess clock with TIME for each side, a duration like 5m.
+            INCREMENT is added to a side's time after each of their moves, e.g.
+            5m+3s. Press space to start the left side's clock and then to end
+            each move, 'p' to pause or resume, and 'r' to reset. The side to
+            move is highlighted, and a side that runs out of time flashes.
+
+    --interval WORK/REST
+            Alternate between counting down WORK in red and REST in green,
+            ringing the bell at the end of each, e.g. --interval 40s/20s. REST
+            can be 0 to skip the rests. The round is shown above the countdown,
+            and space pauses or resumes. The total time is printed on quitting.
+
+    --rounds N
+            Stop the interval timer after N rounds, ringing the bell twice.
+
+    -s, --stopwatch
+            Show a stopwatch instead of the time. Press space to pause or
+            resume, 'r' to reset, and 'l' to record a lap. Laps are listed
+            below the stopwatch and can be scrolled with the up and down keys.
+            The elapsed time is printed when the stopwatch is quit.
+
+ENVIRONMENT
+    CLOCK_COLOR
+            The colour of the clock when --colour isn't given. The value is the
+            same as for --colour.
+
+    NO_COLOR
+            When set to a non-empty value the clock is drawn without colour,
+            even if colours are chosen with the options above, unless
+            --force-colour is given.
+
+FILES
+    $XDG_CONFIG_HOME/7clock/config.toml, ~/.config/7clock/config.toml
+            Default options, one per line as name = value, e.g.
+            show_seconds = true or colour = \"cyan\". The names are:
+            twenty_four_hour, pad_hour, no_period, lowercase_period,
+            dim_period, period_colour, show_seconds, show_tenths, show_date,
+            colour, bg_colour, rainbow, cycle_speed, timezone, utc, format, and
+            separator.
+            Options given on the command line take precedence, and options
+            in the file that conflict with them are ignored.
+
+AUTHOR
+    Wesley Moore <wes@wezm.net>
+
+SEE ALSO
+    https://github.com/wezm/7clock  Source code and issue tracker.",
+        version_string(),
+        bin = "7clock"
+    );
+}
+
+pub fn version_string() -> String {
+    format!(
+        "{} version {}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// The version and details of the build as a JSON object.
+fn version_json() -> String {
+    let fields = [
+        ("name", env!("CARGO_PKG_NAME")),
+        ("version", env!("CARGO_PKG_VERSION")),
+        ("rustc", env!("SEVEN_CLOCK_RUSTC")),
+        ("target", env!("SEVEN_CLOCK_TARGET")),
+        ("profile", env!("SEVEN_CLOCK_PROFILE")),
+    ];
+    let object = fields
+        .iter()
+        .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!("{{{}}}", object)
+}
+
+/// The instant the system booted, from `/proc/uptime`. When the uptime can't be read the
+/// instant the clock started is used instead, after printing a warning.
+fn boot_time() -> Instant {
+    let now = Instant::now();
+    let uptime = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|contents| contents.split_whitespace().next()?.parse::<f64>().ok())
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok());
+    match uptime.and_then(|uptime| now.checked_sub(uptime)) {
+        Some(booted) => booted,
+        None => {
+            eprintln!("warning: unable to read the system uptime, showing the time since 7clock started instead");
+            now
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::time;
+
+    /// Parse `args` without reading the environment or configuration file.
+    fn parse_test(args: &[&str]) -> Result<Options, Error> {
+        parse(args.iter().map(|arg| arg.to_string()), |_| Ok(()))
+    }
+
+    fn usage_error(args: &[&str]) -> String {
+        match parse_test(args) {
+            Err(Error::Usage(message)) => message,
+            Err(err) => panic!("expected a usage error for {:?}, got {:?}", args, err),
+            Ok(_) => panic!("expected a usage error for {:?}", args),
+        }
+    }
+
+    #[test]
+    fn parse_durations() {
+        let valid = [
+            ("0", 0),
+            ("90", 90),
+            ("1:30", 90),
+            ("90:00", 5400),
+            ("1:00:00", 3600),
+            ("100:59:59", 363_599),
+            ("45s", 45),
+            ("25m", 1500),
+            ("1h30m", 5400),
+            ("1h0m1s", 3601),
+            ("2h5s", 7205),
+        ];
+        for (s, secs) in valid {
+            assert_eq!(
+                parse_duration(s).unwrap(),
+                Duration::from_secs(secs),
+                "{}",
+                s
+            );
+        }
+        let invalid = [
+            "",
+            ":",
+            "1:",
+            "-1",
+            "1.5",
+            "1:60",
+            "1:60:00",
+            "1:00:60",
+            "1:2:3:4",
+            "1d",
+            "h",
+            "1hm",
+            "1m1h",
+            "1h1h",
+            "1s1s",
+            "1 h",
+            "18446744073709551616",
+            "5124095576030432:00:00",
+            "5124095576030432h",
+        ];
+        for s in invalid {
+            assert!(
+                matches!(parse_duration(s), Err(Error::Usage(_))),
+                "{:?} should be invalid",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn parse_unit_durations() {
+        let cases = [
+            ("1h", Some(3600)),
+            ("1m", Some(60)),
+            ("1s", Some(1)),
+            ("1h1m1s", Some(3661)),
+            ("0h0m0s", Some(0)),
+            ("120m", Some(7200)),
+            // Each unit needs a number
+            ("h", None),
+            ("1hm", None),
+            ("1h30", None),
+            // Units are given once, in order
+            ("1m1m", None),
+            ("1s1m", None),
+            ("1x", None),
+            ("18446744073709551615s", Some(u64::MAX)),
+            ("18446744073709551616s", None),
+            ("18446744073709551615h", None),
+            ("18446744073709551615s1m", None),
+        ];
+        for (s, secs) in cases {
+            assert_eq!(parse_units(s), secs, "{}", s);
+        }
+    }
+
+    #[test]
+    fn parse_times() {
+        let valid = [
+            ("0:00", time!(0:00)),
+            ("7:30", time!(7:30)),
+            ("23:59:59", time!(23:59:59)),
+            ("12:00am", time!(0:00)),
+            ("12:30am", time!(0:30)),
+            ("12:00pm", time!(12:00)),
+            ("12:30pm", time!(12:30)),
+            ("1:00am", time!(1:00)),
+            ("11:59:59pm", time!(23:59:59)),
+            ("7:30 PM", time!(19:30)),
+        ];
+        for (s, time) in valid {
+            assert_eq!(parse_time(s).unwrap(), time, "{}", s);
+        }
+        let invalid = [
+            "", "7", "7pm", "24:00", "23:60", "23:59:60", "0:00am", "0:30pm", "12am", "13:00pm",
+            "1:2:3:4", "-1:00", "7:30 xm",
+        ];
+        for s in invalid {
+            assert!(
+                matches!(parse_time(s), Err(Error::Usage(_))),
+                "{:?} should be invalid",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn parse_offsets() {
+        let valid = [
+            ("+00:00", (0, 0)),
+            ("-00:00", (0, 0)),
+            ("+05:30", (5, 30)),
+            ("-09:30", (-9, -30)),
+            ("+14:00", (14, 0)),
+            ("-14:00", (-14, 0)),
+        ];
+        for (s, (hours, minutes)) in valid {
+            assert_eq!(
+                parse_offset(s).unwrap(),
+                UtcOffset::from_hms(hours, minutes, 0).unwrap(),
+                "{}",
+                s
+            );
+        }
+        let invalid = [
+            "",
+            "+",
+            "05:30",
+            "+5:30",
+            "+05:3",
+            "+0530",
+            "+05:30:00",
+            "++5:30",
+            "+-5:30",
+            "+05:-3",
+            "+05:+3",
+            "+14:01",
+            "+15:00",
+            "+05:60",
+            "+aa:bb",
+            // Multibyte characters, which byte slicing mustn't split
+            "é05:30",
+            "−05:30",
+            "+0٥:30",
+            "+05:٣0",
+            "+05:3é",
+        ];
+        for s in invalid {
+            assert!(
+                matches!(parse_offset(s), Err(Error::Usage(_))),
+                "{:?} should be invalid",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn utc() {
+        let options = parse_test(&["--utc"]).unwrap();
+        assert_eq!(options.now().offset(), UtcOffset::UTC);
+        assert_eq!(
+            usage_error(&["--utc", "--timezone", "UTC"]),
+            "--utc can't be used with --timezone"
+        );
+        assert_eq!(
+            usage_error(&["--timezone", "UTC", "--utc"]),
+            "--utc can't be used with --timezone"
+        );
+    }
+
+    #[test]
+    fn theme_is_the_default() {
+        // Whatever the terminal and NO_COLOR, the colours are kept as they are
+        let args = ["--truecolor", "--force-colour", "--theme", "amber"];
+        let theme = parse_theme("amber").unwrap();
+        let options = parse_test(&args).unwrap();
+        assert_eq!(options.colour, Some(theme.colour));
+        assert_eq!(options.bg_colour, Some(theme.background));
+        // Colours from the environment or configuration file take precedence
+        let options = parse(args.map(String::from), |options| {
+            options.colour = Some(Color::Red);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(options.colour, Some(Color::Red));
+        assert_eq!(options.bg_colour, Some(theme.background));
+        // As do those on the command line, wherever they are
+        let options = parse_test(&[
+            "--bg",
+            "blue",
+            "--truecolor",
+            "--force-colour",
+            "--theme",
+            "amber",
+        ])
+        .unwrap();
+        assert_eq!(options.colour, Some(theme.colour));
+        assert_eq!(options.bg_colour, Some(Color::Blue));
+    }
+
+    #[test]
+    fn one_mode() {
+        assert_eq!(
+            usage_error(&["--countdown", "5m", "--stopwatch"]),
+            "--stopwatch can't be used with --countdown"
+        );
+        assert_eq!(
+            usage_error(&["--hex", "--analog"]),
+            "--analog can't be used with --hex"
+        );
+        assert_eq!(
+            usage_error(&["--pomodoro", "25", "--interval", "1m/30s"]),
+            "--interval can't be used with --pomodoro"
+        );
+        assert_eq!(
+            usage_error(&["--epoch", "--uptime"]),
+            "--uptime can't be used with --epoch"
+        );
+        // Repeating a mode, or giving one of its aliases, is fine
+        assert!(matches!(
+            parse_test(&["--countdown", "5m", "--timer", "10m"]).map(|options| options.mode),
+            Ok(Mode::Countdown(_))
+        ));
+        assert!(matches!(
+            parse_test(&["--hex", "--hex"]).map(|options| options.mode),
+            Ok(Mode::Hex)
+        ));
+    }
+
+    #[test]
+    fn parse_colour_palette_numbers() {
+        assert_eq!(parse_colour("0").unwrap(), Color::AnsiValue(0));
+        assert_eq!(parse_colour("255").unwrap(), Color::AnsiValue(255));
+        assert_eq!(parse_colour("214").unwrap(), Color::AnsiValue(214));
+        assert!(matches!(parse_colour("256"), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn parse_colour_ansi_prefix() {
+        assert_eq!(parse_colour("ansi:0").unwrap(), Color::AnsiValue(0));
+        assert_eq!(parse_colour("ansi:255").unwrap(), Color::AnsiValue(255));
+        assert!(matches!(parse_colour("ansi:256"), Err(Error::Message(_))));
+        assert!(matches!(parse_colour("ansi:red"), Err(Error::Message(_))));
+        assert!(matches!(parse_colour("ansi:"), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn parse_colour_names_and_hex() {
+        assert_eq!(parse_colour("red").unwrap(), Color::Red);
+        assert_eq!(parse_colour("cyan").unwrap(), Color::Cyan);
+        assert_eq!(
+            parse_colour("#fca311").unwrap(),
+            Color::Rgb {
+                r: 0xfc,
+                g: 0xa3,
+                b: 0x11
+            }
+        );
+        assert!(matches!(parse_colour("#fca31"), Err(Error::Message(_))));
+        assert!(matches!(parse_colour("#gggggg"), Err(Error::Message(_))));
+        assert!(matches!(parse_colour("chartreuse"), Err(Error::Message(_))));
+    }
+
+    #[test]
+    fn use_colour_no_color() {
+        let set = Some(OsStr::new("1"));
+        assert!(use_colour(None, false));
+        assert!(use_colour(None, true));
+        assert!(!use_colour(set, false));
+        assert!(use_colour(set, true));
+        // An empty NO_COLOR is the same as it not being set
+        assert!(use_colour(Some(OsStr::new("")), false));
+    }
+
+    #[test]
+    fn no_color_removes_colours() {
+        let coloured = || Options {
+            colour: Some(Color::Red),
+            bg_colour: Some(Color::Blue),
+            ..Options::default()
+        };
+        let mut options = coloured();
+        apply_no_color(&mut options, Some(OsStr::new("1")), false);
+        assert_eq!(options.colour, None);
+        assert_eq!(options.bg_colour, None);
+
+        let mut options = coloured();
+        apply_no_color(&mut options, Some(OsStr::new("1")), true);
+        assert_eq!(options.colour, Some(Color::Red));
+        assert_eq!(options.bg_colour, Some(Color::Blue));
+
+        let mut options = coloured();
+        apply_no_color(&mut options, None, false);
+        assert_eq!(options.colour, Some(Color::Red));
+    }
+
+    #[test]
+    fn separator_width() {
+        for separator in [":", ".", " ", "·", "|"] {
+            assert!(parse_separator(separator).is_ok(), "{:?}", separator);
+        }
+        // Wide characters, emoji, zero width characters, and control characters
+        for separator in [
+            "漢", "：", "🚀", "⚡", "⏰", "🙂", "\u{301}", "\u{200d}", "\u{fe0f}", "\t", "", "::",
+        ] {
+            assert!(parse_separator(separator).is_err(), "{:?}", separator);
+        }
+    }
+
+    #[test]
+    fn parse_hex_non_ascii() {
+        // Six bytes, but not six characters
+        assert!(matches!(parse_hex("ééé"), Err(Error::Message(_))));
+        assert!(matches!(parse_hex("a€bc"), Err(Error::Message(_))));
+    }
+}
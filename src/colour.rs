@@ -0,0 +1,305 @@
+//! Colour conversion and animation helpers.
+
+use crossterm::style::Color;
+
+/// Convert a colour in HSV space to RGB.
+///
+/// `h` is the hue in degrees, `s` and `v` are the saturation and value from 0 to 1.
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> (u8, u8, u8) {
+    let h = h.rem_euclid(360.) / 60.;
+    let c = v * s;
+    let x = c * (1. - (h % 2. - 1.).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.),
+        1 => (x, c, 0.),
+        2 => (0., c, x),
+        3 => (0., x, c),
+        4 => (x, 0., c),
+        _ => (c, 0., x),
+    };
+    let m = v - c;
+    let channel = |value: f32| ((value + m) * 255.).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+/// Convert a colour in RGB to HSV space, the inverse of [`hsv_to_rgb`].
+pub fn rgb_to_hsv((r, g, b): (u8, u8, u8)) -> (f32, f32, f32) {
+    let (r, g, b) = (
+        f32::from(r) / 255.,
+        f32::from(g) / 255.,
+        f32::from(b) / 255.,
+    );
+    let max = r.max(g).max(b);
+    let c = max - r.min(g).min(b);
+    let h = if c == 0. {
+        0.
+    } else if max == r {
+        60. * ((g - b) / c).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / c + 2.)
+    } else {
+        60. * ((r - g) / c + 4.)
+    };
+    let s = if max == 0. { 0. } else { c / max };
+    (h, s, max)
+}
+
+/// `colour` with its hue turned `degrees` around the colour wheel.
+pub fn rotate_hue(colour: Color, degrees: f32) -> Color {
+    let (h, s, v) = rgb_to_hsv(to_rgb(colour));
+    Color::from(hsv_to_rgb(h + degrees, s, v))
+}
+
+/// Returns true if the terminal says it can show RGB colours, in `COLORTERM` or the name
+/// of the terminal type in `TERM`, like xterm-direct.
+pub fn supports_rgb() -> bool {
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    let term = std::env::var("TERM").unwrap_or_default();
+    colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.ends_with("-direct")
+        || term.contains("truecolor")
+        || term.contains("24bit")
+}
+
+/// The nearest colour to `colour` in the 6×6×6 colour cube or greyscale ramp of the 256
+/// colour palette, for terminals that can't show RGB colours. Other colours are returned
+/// as they are.
+pub fn to_ansi(colour: Color) -> Color {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let Color::Rgb { r, g, b } = colour else {
+        return colour;
+    };
+    let nearest_level = |value: u8| (0..6).min_by_key(|&i| LEVELS[i].abs_diff(value)).unwrap();
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let average = (u16::from(r) + u16::from(g) + u16::from(b)) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as usize;
+    let distance = |value: usize| {
+        let (r2, g2, b2) = ansi_to_rgb(value as u8);
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|&(a, b)| u32::from(a.abs_diff(b)).pow(2))
+            .sum::<u32>()
+    };
+    let index = if distance(grey) < distance(cube) {
+        grey
+    } else {
+        cube
+    };
+    Color::AnsiValue(index as u8)
+}
+
+/// The colour `t` seconds into a cycle through every hue that takes `speed` seconds.
+pub fn cycle_color(t: f64, speed: f64) -> Color {
+    let hue = (t / speed).fract() * 360.;
+    Color::from(hsv_to_rgb(hue as f32, 1., 1.))
+}
+
+/// A set of colours chosen with `--theme`.
+pub struct Theme {
+    pub name: &'static str,
+    pub colour: Color,
+    pub background: Color,
+    /// The colour of the seconds and AM/PM, a quieter version of `colour`.
+    pub accent: Color,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// The themes that can be chosen with `--theme`.
+pub const THEMES: [Theme; 6] = [
+    Theme {
+        name: "amber",
+        colour: rgb(0xFF, 0xB0, 0x00),
+        background: rgb(0x00, 0x00, 0x00),
+        accent: rgb(0x99, 0x6A, 0x00),
+    },
+    Theme {
+        name: "dracula",
+        colour: rgb(0xBD, 0x93, 0xF9),
+        background: rgb(0x28, 0x2A, 0x36),
+        accent: rgb(0x62, 0x72, 0xA4),
+    },
+    Theme {
+        name: "gruvbox",
+        colour: rgb(0xFA, 0xBD, 0x2F),
+        background: rgb(0x28, 0x28, 0x28),
+        accent: rgb(0x92, 0x83, 0x74),
+    },
+    Theme {
+        name: "nord",
+        colour: rgb(0x88, 0xC0, 0xD0),
+        background: rgb(0x2E, 0x34, 0x40),
+        accent: rgb(0x81, 0xA1, 0xC1),
+    },
+    Theme {
+        name: "phosphor",
+        colour: rgb(0x33, 0xFF, 0x33),
+        background: rgb(0x00, 0x00, 0x00),
+        accent: rgb(0x1A, 0x80, 0x1A),
+    },
+    Theme {
+        name: "solarized",
+        colour: rgb(0x26, 0x8B, 0xD2),
+        background: rgb(0x00, 0x2B, 0x36),
+        accent: rgb(0x58, 0x6E, 0x75),
+    },
+];
+
+/// The colour of each hour of the day, starting at midnight.
+const HOUR_COLOURS: [(u8, u8, u8); 24] = [
+    (40, 40, 160),
+    (40, 40, 150),
+    (50, 40, 150),
+    (60, 50, 160),
+    (90, 60, 170),
+    (160, 80, 150),
+    (240, 120, 60),
+    (255, 160, 50),
+    (255, 200, 80),
+    (255, 230, 120),
+    (255, 245, 170),
+    (255, 255, 210),
+    (255, 255, 255),
+    (255, 255, 210),
+    (255, 245, 170),
+    (255, 230, 120),
+    (255, 200, 80),
+    (255, 150, 50),
+    (240, 90, 40),
+    (200, 50, 60),
+    (140, 40, 110),
+    (90, 40, 140),
+    (60, 40, 150),
+    (45, 40, 155),
+];
+
+/// The colour at `minute` past `hour`, fading from the colour of the hour to the next.
+pub fn hour_colour(hour: u8, minute: u8) -> Color {
+    let hour = usize::from(hour % 24);
+    lerp(
+        Color::from(HOUR_COLOURS[hour]),
+        Color::from(HOUR_COLOURS[(hour + 1) % 24]),
+        f32::from(minute) / 60.,
+    )
+}
+
+/// The approximate RGB value of `colour`.
+///
+/// Named colours use the xterm defaults, the actual colour depends on the terminal's
+/// palette.
+pub fn to_rgb(colour: Color) -> (u8, u8, u8) {
+    match colour {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => ansi_to_rgb(value),
+        Color::Black => (0, 0, 0),
+        Color::DarkRed => (205, 0, 0),
+        Color::DarkGreen => (0, 205, 0),
+        Color::DarkYellow => (205, 205, 0),
+        Color::DarkBlue => (0, 0, 238),
+        Color::DarkMagenta => (205, 0, 205),
+        Color::DarkCyan => (0, 205, 205),
+        Color::Grey => (229, 229, 229),
+        Color::DarkGrey => (127, 127, 127),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::Blue => (92, 92, 255),
+        Color::Magenta => (255, 0, 255),
+        Color::Cyan => (0, 255, 255),
+        Color::White | Color::Reset => (255, 255, 255),
+    }
+}
+
+/// The RGB value of an entry in the 256 colour palette.
+fn ansi_to_rgb(value: u8) -> (u8, u8, u8) {
+    const NAMED: [Color; 16] = [
+        Color::Black,
+        Color::DarkRed,
+        Color::DarkGreen,
+        Color::DarkYellow,
+        Color::DarkBlue,
+        Color::DarkMagenta,
+        Color::DarkCyan,
+        Color::Grey,
+        Color::DarkGrey,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+    match value {
+        0..=15 => to_rgb(NAMED[usize::from(value)]),
+        // 6×6×6 colour cube
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = value - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        // Greyscale ramp
+        _ => {
+            let grey = 8 + (value - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
+}
+
+/// Linearly interpolate between `from` and `to` in sRGB space, `t` is from 0 to 1.
+pub fn lerp(from: Color, to: Color, t: f32) -> Color {
+    let (from, to) = (to_rgb(from), to_rgb(to));
+    let channel = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    Color::from((
+        channel(from.0, to.0),
+        channel(from.1, to.1),
+        channel(from.2, to.2),
+    ))
+}
+
+/// A darker version of `colour`, at half the brightness.
+pub fn darken(colour: Color) -> Color {
+    lerp(colour, Color::Black, 0.5)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_ansi_cube() {
+        assert_eq!(
+            to_ansi(Color::from((0x5f, 0x87, 0xaf))),
+            Color::AnsiValue(67)
+        );
+        assert_eq!(
+            to_ansi(Color::from((0xfc, 0xa3, 0x11))),
+            Color::AnsiValue(214)
+        );
+    }
+
+    #[test]
+    fn to_ansi_grey() {
+        assert_eq!(
+            to_ansi(Color::from((0x80, 0x80, 0x80))),
+            Color::AnsiValue(244)
+        );
+    }
+
+    #[test]
+    fn to_ansi_black_and_white() {
+        assert_eq!(to_ansi(Color::from((0, 0, 0))), Color::AnsiValue(16));
+        assert_eq!(to_ansi(Color::from((255, 255, 255))), Color::AnsiValue(231));
+    }
+
+    #[test]
+    fn to_ansi_passes_other_colours_through() {
+        assert_eq!(to_ansi(Color::Red), Color::Red);
+        assert_eq!(to_ansi(Color::AnsiValue(42)), Color::AnsiValue(42));
+        assert_eq!(to_ansi(Color::Reset), Color::Reset);
+    }
+}
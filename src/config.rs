@@ -0,0 +1,352 @@
+//! Default options read from `~/.config/7clock/config.toml`.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crossterm::style::Color;
+use serde::Deserialize;
+use time::format_description;
+
+use crate::args::{parse_colour, parse_separator, parse_timezone};
+use crate::tz::TimeZone;
+use crate::{Error, Mode, Options};
+
+/// The options set in the configuration file, `None` for those that aren't.
+#[derive(Default)]
+pub struct PartialOptions {
+    twenty_four_hour: Option<bool>,
+    pad_hour: Option<bool>,
+    no_period: Option<bool>,
+    lowercase_period: Option<bool>,
+    dim_period: Option<bool>,
+    period_colour: Option<Color>,
+    show_seconds: Option<bool>,
+    show_tenths: Option<bool>,
+    show_date: Option<bool>,
+    colour: Option<Color>,
+    bg_colour: Option<Color>,
+    rainbow: Option<bool>,
+    cycle_speed: Option<f64>,
+    timezone: Option<TimeZone>,
+    utc: Option<bool>,
+    format: Option<String>,
+    separator: Option<char>,
+}
+
+/// The configuration file as it's written, before the values are checked. The
+/// American spellings of the colour options are accepted too.
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    twenty_four_hour: Option<bool>,
+    pad_hour: Option<bool>,
+    no_period: Option<bool>,
+    lowercase_period: Option<bool>,
+    dim_period: Option<bool>,
+    #[serde(alias = "period_color")]
+    period_colour: Option<String>,
+    show_seconds: Option<bool>,
+    show_tenths: Option<bool>,
+    show_date: Option<bool>,
+    #[serde(alias = "color")]
+    colour: Option<String>,
+    #[serde(alias = "bg_color")]
+    bg_colour: Option<String>,
+    rainbow: Option<bool>,
+    cycle_speed: Option<f64>,
+    timezone: Option<String>,
+    utc: Option<bool>,
+    format: Option<String>,
+    separator: Option<String>,
+}
+
+/// Read the configuration file. A missing file is the same as an empty one.
+pub fn load_config() -> Result<PartialOptions, Error> {
+    let path = match config_path() {
+        Some(path) => path,
+        None => return Ok(PartialOptions::default()),
+    };
+    match fs::read_to_string(&path) {
+        Ok(contents) => parse_config(&contents)
+            .map_err(|err| Error::Message(format!("{}: {}", path.display(), err))),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(PartialOptions::default()),
+        Err(err) => Err(Error::Message(format!(
+            "unable to read {}: {}",
+            path.display(),
+            err
+        ))),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/7clock/config.toml`, falling back on `~/.config` when
+/// `XDG_CONFIG_HOME` isn't set.
+fn config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("7clock").join("config.toml"))
+}
+
+fn parse_config(contents: &str) -> Result<PartialOptions, String> {
+    let config: Config = toml::from_str(contents).map_err(|err| err.to_string())?;
+    if let Some(format) = &config.format {
+        // Checked like --format, even if the format is overridden by the command line
+        format_description::parse(format)
+            .map_err(|err| format!("invalid format '{}': {}", format, err))?;
+    }
+    let cycle_speed = match config.cycle_speed {
+        Some(speed) if !(speed > 0. && speed.is_finite()) => {
+            return Err("cycle_speed must be a number greater than 0".into())
+        }
+        speed => speed,
+    };
+    let partial = PartialOptions {
+        twenty_four_hour: config.twenty_four_hour,
+        pad_hour: config.pad_hour,
+        no_period: config.no_period,
+        lowercase_period: config.lowercase_period,
+        dim_period: config.dim_period,
+        period_colour: parse_field("period_colour", config.period_colour, parse_colour)?,
+        show_seconds: config.show_seconds,
+        show_tenths: config.show_tenths,
+        show_date: config.show_date,
+        colour: parse_field("colour", config.colour, parse_colour)?,
+        bg_colour: parse_field("bg_colour", config.bg_colour, parse_colour)?,
+        rainbow: config.rainbow,
+        cycle_speed,
+        timezone: parse_field("timezone", config.timezone, parse_timezone)?,
+        utc: config.utc,
+        format: config.format,
+        separator: parse_field("separator", config.separator, parse_separator)?,
+    };
+    partial.check_conflicts()?;
+    Ok(partial)
+}
+
+/// Parse the value of `key`, if it's set, like the command line argument it stands for.
+fn parse_field<T>(
+    key: &str,
+    value: Option<String>,
+    parse: impl Fn(&str) -> Result<T, Error>,
+) -> Result<Option<T>, String> {
+    value
+        .map(|value| parse(&value).map_err(|err| format!("{}: {}", key, message(err))))
+        .transpose()
+}
+
+/// The message of an error from parsing a command line argument.
+fn message(err: Error) -> String {
+    match err {
+        Error::Usage(message) | Error::Message(message) => message,
+        err => err.to_string(),
+    }
+}
+
+impl PartialOptions {
+    /// Report the options that can't be used together, like the command line does.
+    fn check_conflicts(&self) -> Result<(), String> {
+        let on = |value: Option<bool>| value == Some(true);
+        let twenty_four_hour = on(self.twenty_four_hour);
+        let format = self.format.is_some();
+        let conflicts = [
+            (
+                "no_period",
+                on(self.no_period),
+                "twenty_four_hour",
+                twenty_four_hour,
+            ),
+            (
+                "period_colour",
+                self.period_colour.is_some(),
+                "twenty_four_hour",
+                twenty_four_hour,
+            ),
+            ("twenty_four_hour", twenty_four_hour, "format", format),
+            ("pad_hour", on(self.pad_hour), "format", format),
+            ("show_seconds", on(self.show_seconds), "format", format),
+            ("show_tenths", on(self.show_tenths), "format", format),
+            ("utc", on(self.utc), "timezone", self.timezone.is_some()),
+        ];
+        match conflicts.iter().find(|(_, a, _, b)| *a && *b) {
+            Some((a, _, b, _)) => Err(format!("{} can't be used with {}", a, b)),
+            None => Ok(()),
+        }
+    }
+
+    /// Set the options in `options` that are set in the configuration file and
+    /// weren't given on the command line. Options that conflict with those on the
+    /// command line are ignored, so that the command line takes precedence.
+    pub fn apply(self, options: &mut Options) {
+        let PartialOptions {
+            twenty_four_hour,
+            pad_hour,
+            no_period,
+            lowercase_period,
+            dim_period,
+            period_colour,
+            show_seconds,
+            show_tenths,
+            show_date,
+            colour,
+            bg_colour,
+            rainbow,
+            cycle_speed,
+            timezone,
+            utc,
+            format,
+            separator,
+        } = self;
+        let analog = matches!(options.mode, Mode::Analog);
+        let epoch = matches!(options.mode, Mode::Epoch);
+        let hex = matches!(options.mode, Mode::Hex);
+        let zone_given = options.offset.is_some() || !options.world.is_empty();
+        // Everything is decided before `options` changes so that only conflicts
+        // with the command line count, those within the file have been reported
+        let unless = |value: Option<bool>, conflict: bool| value == Some(true) && !conflict;
+        let twenty_four_hour = unless(
+            twenty_four_hour,
+            options.no_period
+                || options.period_style.colour.is_some()
+                || options.format.is_some()
+                || epoch,
+        );
+        let pad_hour = unless(pad_hour, options.format.is_some());
+        let no_period = unless(no_period, options.twenty_four_hour);
+        let period_colour = period_colour.filter(|_| !options.twenty_four_hour);
+        let show_seconds = unless(
+            show_seconds,
+            options.format.is_some() || options.seconds_bar || epoch,
+        );
+        let show_tenths = unless(
+            show_tenths,
+            options.format.is_some() || options.seconds_bar || analog || epoch || hex,
+        );
+        let show_date = unless(show_date, analog);
+        let colour = colour.filter(|_| !options.colour_by_hour);
+        let rainbow = unless(rainbow, options.colour_by_hour);
+        let timezone = timezone.filter(|_| !options.utc && !zone_given);
+        let utc = unless(utc, options.timezone.is_some() || zone_given);
+        let format = format.filter(|_| {
+            !(options.twenty_four_hour
+                || options.pad_hour
+                || options.show_seconds
+                || options.show_tenths
+                || options.iso
+                || analog
+                || epoch
+                || hex)
+        });
+        options.twenty_four_hour |= twenty_four_hour;
+        options.pad_hour |= pad_hour;
+        options.no_period |= no_period;
+        options.lowercase_period |= lowercase_period == Some(true);
+        options.period_style.dim |= dim_period == Some(true);
+        options.period_style.colour = options.period_style.colour.or(period_colour);
+        options.show_seconds |= show_seconds;
+        options.show_tenths |= show_tenths;
+        options.show_date |= show_date;
+        options.colour = options.colour.or(colour);
+        options.bg_colour = options.bg_colour.or(bg_colour);
+        options.rainbow |= rainbow;
+        options.cycle_speed = options.cycle_speed.or(cycle_speed);
+        options.timezone = options.timezone.take().or(timezone);
+        options.utc |= utc;
+        options.format = options.format.take().or(format);
+        options.separator = options.separator.or(separator);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let config = parse_config(
+            r##"
+            # Comments and blank lines are ignored
+            dim_period = true # after a value too
+            show_date = false
+            colour = "#ff0000"
+            bg_color = 'blue'
+            cycle_speed = 2
+            format = "[hour]:[minute] \"#\""
+            separator = "\u00b7"
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.dim_period, Some(true));
+        assert_eq!(config.show_date, Some(false));
+        assert_eq!(config.no_period, None);
+        assert_eq!(
+            config.colour,
+            Some(Color::Rgb {
+                r: 0xff,
+                g: 0,
+                b: 0
+            })
+        );
+        assert_eq!(config.bg_colour, Some(Color::Blue));
+        assert_eq!(config.cycle_speed, Some(2.));
+        assert_eq!(config.format.as_deref(), Some("[hour]:[minute] \"#\""));
+        assert_eq!(config.separator, Some('·'));
+    }
+
+    #[test]
+    fn invalid_values() {
+        for contents in [
+            "unknown = true",
+            "pad_hour = 1",
+            "pad_hour = \"true\"",
+            "pad_hour = yes",
+            "colour = 1",
+            "format = \"unterminated",
+            "pad_hour",
+        ] {
+            assert!(parse_config(contents).is_err(), "{:?}", contents);
+        }
+        let err = |contents| parse_config(contents).err().unwrap();
+        assert_eq!(
+            err("cycle_speed = 0"),
+            "cycle_speed must be a number greater than 0"
+        );
+        assert!(err("colour = \"nope\"").starts_with("colour: unable to parse colour"));
+        assert!(err("separator = \"::\"").starts_with("separator: invalid separator"));
+        assert!(err("format = \"[hour\"").starts_with("invalid format '[hour'"));
+    }
+
+    #[test]
+    fn conflicts_in_the_file() {
+        let err = parse_config("twenty_four_hour = true\nperiod_colour = \"red\"")
+            .err()
+            .unwrap();
+        assert_eq!(err, "period_colour can't be used with twenty_four_hour");
+        let err = parse_config("pad_hour = true\nformat = \"[hour]:[minute]\"")
+            .err()
+            .unwrap();
+        assert_eq!(err, "pad_hour can't be used with format");
+    }
+
+    #[test]
+    fn command_line_takes_precedence() {
+        let config = parse_config("period_colour = \"red\"\npad_hour = true").unwrap();
+        let mut options = Options {
+            twenty_four_hour: true,
+            ..Options::default()
+        };
+        config.apply(&mut options);
+        assert!(options.period_style.colour.is_none());
+        assert!(options.pad_hour);
+
+        let config = parse_config("twenty_four_hour = true").unwrap();
+        let mut options = Options {
+            no_period: true,
+            ..Options::default()
+        };
+        config.apply(&mut options);
+        assert!(!options.twenty_four_hour);
+    }
+}
@@ -0,0 +1,230 @@
+//! Multi-row fonts for drawing the time larger than a single character cell.
+
+/// Seven-segment digits three rows tall drawn with box-drawing characters.
+const LARGE_DIGITS: [[&str; 3]; 10] = [
+    ["┌─┐", "│ │", "└─┘"],
+    ["  ╷", "  │", "  ╵"],
+    ["╶─┐", "┌─┘", "└─╴"],
+    ["╶─┐", "╶─┤", "╶─┘"],
+    ["╷ ╷", "└─┤", "  ╵"],
+    ["┌─╴", "└─┐", "╶─┘"],
+    ["┌─╴", "├─┐", "└─┘"],
+    ["╶─┐", "  │", "  ╵"],
+    ["┌─┐", "├─┤", "└─┘"],
+    ["┌─┐", "└─┤", "╶─┘"],
+];
+
+/// Seven-segment digits three rows tall drawn with ASCII characters.
+const ASCII_DIGITS: [[&str; 3]; 10] = [
+    [" _ ", "| |", "|_|"],
+    ["   ", "  |", "  |"],
+    [" _ ", " _|", "|_ "],
+    [" _ ", " _|", " _|"],
+    ["   ", "|_|", "  |"],
+    [" _ ", "|_ ", " _|"],
+    [" _ ", "|_ ", "|_|"],
+    [" _ ", "  |", "  |"],
+    [" _ ", "|_|", "|_|"],
+    [" _ ", "|_|", " _|"],
+];
+
+/// Digits five rows tall drawn with full blocks.
+const BIG_DIGITS: [[&str; 5]; 10] = [
+    ["███", "█ █", "█ █", "█ █", "███"],
+    ["  █", "  █", "  █", "  █", "  █"],
+    ["███", "  █", "███", "█  ", "███"],
+    ["███", "  █", "███", "  █", "███"],
+    ["█ █", "█ █", "███", "  █", "  █"],
+    ["███", "█  ", "███", "  █", "███"],
+    ["███", "█  ", "███", "█ █", "███"],
+    ["███", "  █", "  █", "  █", "  █"],
+    ["███", "█ █", "███", "█ █", "███"],
+    ["███", "█ █", "███", "  █", "███"],
+];
+
+/// The characters for lit and unlit bits of the binary clock.
+const BINARY_ON: char = '●';
+const BINARY_OFF: char = '○';
+
+/// Convert `s` into three rows of text with each digit drawn three rows tall.
+///
+/// Other characters are placed on the middle row, except `.`, which sits on the bottom
+/// row. Digits are separated from their neighbours by a blank column.
+pub fn segmentify_large(s: &str) -> Vec<String> {
+    draw(s, 3, |ch| match ch.to_digit(10) {
+        Some(digit) => LARGE_DIGITS[digit as usize].map(String::from).to_vec(),
+        None if ch == '.' => place(ch, 2, 3),
+        None => place(ch, 1, 3),
+    })
+}
+
+/// Like `segmentify_large` but the digits are drawn with `_` and `|` so they can be shown
+/// with any font.
+pub fn segmentify_ascii(s: &str) -> Vec<String> {
+    draw(s, 3, |ch| match ch.to_digit(10) {
+        Some(digit) => ASCII_DIGITS[digit as usize].map(String::from).to_vec(),
+        None if ch == '.' => place(ch, 2, 3),
+        None => place(ch, 1, 3),
+    })
+}
+
+/// Convert `s` into five rows of text with each digit drawn three columns wide and five
+/// rows tall.
+///
+/// The colon is drawn as two blocks, `.` sits on the bottom row, and other characters are
+/// placed on the middle row.
+pub fn segmentify_big(s: &str) -> Vec<String> {
+    draw(s, 5, |ch| match ch.to_digit(10) {
+        Some(digit) => BIG_DIGITS[digit as usize].map(String::from).to_vec(),
+        None if ch == ':' => [" ", "█", " ", "█", " "].map(String::from).to_vec(),
+        None if ch == '.' => place('█', 4, 5),
+        None => place(ch, 2, 5),
+    })
+}
+
+/// Convert `s` into two rows of Braille patterns with each digit drawn as a dot matrix
+/// three dots wide and seven tall.
+///
+/// The colon is drawn as two dots, `.` as one dot on the bottom row, and other characters
+/// are placed on the bottom row.
+pub fn segmentify_braille(s: &str) -> Vec<String> {
+    draw(s, 2, |ch| match ch.to_digit(10) {
+        Some(digit) => braille_digit(digit),
+        None if ch == ':' => vec!["⠄".to_string(), "⠂".to_string()],
+        None if ch == '.' => place('⠄', 1, 2),
+        None => place(ch, 1, 2),
+    })
+}
+
+/// The two rows of Braille patterns, two cells wide, that draw `digit`.
+///
+/// The dots are the blocks of `BIG_DIGITS` with the vertical strokes doubled in height so
+/// that the digit fills seven of the eight rows of dots.
+fn braille_digit(digit: u32) -> Vec<String> {
+    // The bit of a Braille pattern for each row of dots in the left column of a cell, the
+    // right column is three bits higher for the first three rows
+    const DOTS: [u32; 4] = [0x01, 0x02, 0x04, 0x40];
+    let big = BIG_DIGITS[digit as usize].map(|row| row.chars().collect::<Vec<_>>());
+    let dot_rows = [0, 1, 1, 2, 3, 3, 4].map(|row| &big[row]);
+    (0..2)
+        .map(|cell_row| {
+            (0..2)
+                .map(|cell_col| {
+                    let mut pattern = 0;
+                    for (y, dot) in DOTS.into_iter().enumerate() {
+                        for x in 0..2 {
+                            let lit = dot_rows
+                                .get(cell_row * 4 + y)
+                                .and_then(|row| row.get(cell_col * 2 + x))
+                                == Some(&'█');
+                            if lit {
+                                pattern |= match (x, y) {
+                                    (1, 3) => 0x80,
+                                    (1, _) => dot << 3,
+                                    _ => dot,
+                                };
+                            }
+                        }
+                    }
+                    char::from_u32(0x2800 + pattern).unwrap()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Convert `s` into four rows of text with each digit drawn as a column of its binary
+/// coded decimal bits, the most significant at the top.
+///
+/// Separators become blank columns and other characters are placed on the bottom row.
+pub fn segmentify_binary(s: &str) -> Vec<String> {
+    draw(s, 4, |ch| match ch.to_digit(10) {
+        Some(digit) => (0..4)
+            .rev()
+            .map(|bit| {
+                let on = digit & (1 << bit) != 0;
+                String::from(if on { BINARY_ON } else { BINARY_OFF })
+            })
+            .collect(),
+        None if ch == ':' || ch == '.' => place(' ', 0, 4),
+        None => place(ch, 3, 4),
+    })
+}
+
+/// A row labelling the columns of `segmentify_binary(s)`, H, M, and S under the digits
+/// of the hours, minutes, and seconds.
+pub fn binary_legend(s: &str) -> String {
+    let mut group = 0;
+    let mut rows = draw(s, 1, |ch| {
+        let label = match ch {
+            ':' => {
+                group += 1;
+                ' '
+            }
+            '0'..='9' => ['H', 'M', 'S'].get(group).copied().unwrap_or(' '),
+            _ => ' ',
+        };
+        vec![label.to_string()]
+    });
+    rows.remove(0)
+}
+
+/// Lay out the `height` rows of the glyph of each character of `s` side by side. Digits
+/// are separated from their neighbours by a blank column.
+fn draw(s: &str, height: usize, mut glyph: impl FnMut(char) -> Vec<String>) -> Vec<String> {
+    let mut rows = vec![String::new(); height];
+    let mut prev_digit = None;
+    for ch in s.chars() {
+        let digit = ch.is_ascii_digit();
+        if prev_digit == Some(true) || (prev_digit.is_some() && digit) {
+            rows.iter_mut().for_each(|row| row.push(' '));
+        }
+        for (row, part) in rows.iter_mut().zip(glyph(ch)) {
+            row.push_str(&part);
+        }
+        prev_digit = Some(digit);
+    }
+    rows
+}
+
+/// A glyph `height` rows tall with `ch` on row `on` and blanks on the others.
+fn place(ch: char, on: usize, height: usize) -> Vec<String> {
+    (0..height)
+        .map(|i| String::from(if i == on { ch } else { ' ' }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn place_on_one_row() {
+        assert_eq!(place(':', 1, 3), [" ", ":", " "]);
+        assert_eq!(place('.', 2, 3), [" ", " ", "."]);
+        assert_eq!(place(' ', 0, 1), [" "]);
+    }
+
+    #[test]
+    fn draw_separates_digits() {
+        let glyph = |ch: char| vec![ch.to_string()];
+        assert_eq!(draw("12", 1, glyph), ["1 2"]);
+        assert_eq!(draw("1:2", 1, glyph), ["1 : 2"]);
+        assert_eq!(draw("AM", 1, glyph), ["AM"]);
+        assert_eq!(draw("", 2, glyph), ["", ""]);
+    }
+
+    #[test]
+    fn large_digits() {
+        assert_eq!(
+            segmentify_large("1:0"),
+            ["  ╷   ┌─┐", "  │ : │ │", "  ╵   └─┘"]
+        );
+    }
+
+    #[test]
+    fn binary_digits() {
+        assert_eq!(segmentify_binary("5"), ["○", "●", "○", "●"]);
+        assert_eq!(binary_legend("12:34"), "H H   M M");
+    }
+}
@@ -0,0 +1,366 @@
+//! Positioning the lines of the clock on the screen.
+
+use std::ops::{Range, RangeInclusive};
+
+use time::format_description::FormatItem;
+use unicode_width::UnicodeWidthStr;
+
+use crate::render::{clock_text, ring_radii, spans_width, vertical_spans};
+use crate::{Mode, Options, Side};
+
+/// The size of the clock at scale 1.
+pub struct ClockSize {
+    /// The width of the widest line.
+    pub width: usize,
+    /// The width of the widest line and the number of lines of the time stacked by
+    /// `--vertical`.
+    pub stacked: Option<(usize, u16)>,
+    /// The width of the widest of the lines above and below the time.
+    pub lines: usize,
+}
+
+/// Where the clock is positioned along one axis of the screen.
+#[derive(Clone, Copy, Default)]
+pub enum Anchor {
+    #[default]
+    Center,
+    /// Offset from the top or left of the screen.
+    Absolute(u16),
+    /// Offset from the bottom or right of the screen, 0 is the last row or column.
+    RelativeFromEnd(u16),
+}
+
+/// The position of each line of the display, placed as a block according to the
+/// `--row` and `--col` options.
+pub struct Layout {
+    pub columns: u16,
+    pub rows: u16,
+    /// How lines are positioned horizontally.
+    col: Anchor,
+    /// The row of the title, above or below the time.
+    pub title_row: Option<u16>,
+    /// The row of the phase of the Pomodoro timer, between the title and the time.
+    pub phase_row: Option<u16>,
+    /// The width of the widest of the lines above and below the time.
+    pub lines: usize,
+    pub time_row: u16,
+    /// The number of rows the time occupies, starting at `time_row`.
+    pub clock_height: u16,
+    /// The number of rows each line of the time occupies.
+    pub line_height: u16,
+    /// Whether the time is stacked one part per line. It's drawn on one line when the
+    /// terminal is too short even if `--vertical` was given.
+    pub vertical: bool,
+    /// The number of times bigger each character of the time is drawn.
+    pub scale: u16,
+    /// The row of the seconds bar.
+    pub bar_row: Option<u16>,
+    /// The row of the hour progress bar, below the seconds bar.
+    pub hour_bar_row: Option<u16>,
+    pub date_row: Option<u16>,
+    /// The row of the ISO week number, below the date.
+    pub week_row: Option<u16>,
+    /// The row of the day of the year, below the week number.
+    pub day_row: Option<u16>,
+    /// The rows the lap list of the stopwatch occupies.
+    pub lap_rows: Option<Range<u16>>,
+    /// The top and bottom rows of the border, and the number of columns inside it.
+    pub border: Option<(u16, u16, usize)>,
+    /// Whether the ring of seconds is shown, it's left out when it would overlap the clock.
+    pub ring: bool,
+    /// How many columns and rows a centred clock is moved from the centre.
+    pub offset: (i16, i16),
+    /// How far a centred clock can be moved from the centre in each direction before it
+    /// would go off the screen.
+    pub room: (i16, i16),
+}
+
+/// The width of the widest of the lines drawn above and below the time, once the
+/// interval timer has `completed` rounds.
+pub fn widest_line(options: &Options, completed: u32) -> usize {
+    let title = options.title.as_deref().map(UnicodeWidthStr::width);
+    let date = options.show_date.then(|| {
+        let format = options.date_format().unwrap_or_default();
+        options
+            .now()
+            .format(&format)
+            .map_or(0, |date| date.chars().count())
+    });
+    let phase = match &options.mode {
+        // Without a limit the round keeps growing, so there's room for the current one
+        Mode::Pomodoro(cfg) if cfg.interval => Some(match cfg.rounds {
+            Some(rounds) => format!("ROUND {0}/{0} REST (PAUSED)", rounds).len(),
+            None => format!("ROUND {} REST (PAUSED)", completed.saturating_add(1)).len(),
+        }),
+        Mode::Pomodoro(_) => Some("LONG BREAK (PAUSED)".len()),
+        _ => None,
+    };
+    [
+        title,
+        date,
+        options.week.then_some("W00".len()),
+        options.day_of_year.then_some("000/000".len()),
+        phase,
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+    .unwrap_or(0)
+}
+
+/// The size of the clock at scale 1, used to choose a scale that fits, before any round
+/// of the interval timer is completed.
+pub fn clock_size(options: &Options, format: &[FormatItem]) -> ClockSize {
+    let width = options.font.render(&clock_text(options, format))[0]
+        .chars()
+        .count();
+    let stacked = options.vertical.then(|| {
+        let lines = vertical_spans(options, format, options.now());
+        let width = lines
+            .iter()
+            .map(|spans| spans_width(options, spans))
+            .max()
+            .unwrap_or(0);
+        (width, lines.len() as u16)
+    });
+    ClockSize {
+        width,
+        stacked,
+        lines: widest_line(options, 0),
+    }
+}
+
+impl Layout {
+    /// The layout of a clock of `size` at scale 1. The scale is reduced from the one chosen
+    /// if the clock wouldn't fit otherwise.
+    ///
+    /// A centred clock is moved `offset` columns and rows from the centre, as far as it
+    /// can be without going off the screen.
+    pub fn new(
+        options: &Options,
+        columns: u16,
+        rows: u16,
+        size: &ClockSize,
+        offset: (i16, i16),
+    ) -> Self {
+        let bar =
+            (options.seconds_bar || options.progress_bar) && matches!(options.mode, Mode::Clock);
+        let title_above = options.title.is_some() && matches!(options.title_side, Side::Before);
+        let title_below = options.title.is_some() && matches!(options.title_side, Side::After);
+        // The lines below the time, in order
+        let hour_bar = options.hour_progress && matches!(options.mode, Mode::Clock);
+        let below = [
+            bar,
+            hour_bar,
+            title_below,
+            options.show_date,
+            options.week,
+            options.day_of_year,
+            options.border,
+        ];
+        let below_height = below.iter().filter(|&&shown| shown).count() as u16;
+        // The lines above the time, in order
+        let above = [
+            options.border,
+            title_above,
+            matches!(options.mode, Mode::Pomodoro(_)),
+        ];
+        // The sides of the border and a column of space inside each of them
+        let border_width = if options.border { 4 } else { 0 };
+        let above_height = above.iter().filter(|&&shown| shown).count() as u16;
+        let fits = |width: usize, lines: u16, scale: u16| {
+            above_height + lines * options.font.height() * scale + below_height <= rows
+                && width * usize::from(scale) + border_width <= usize::from(columns)
+        };
+        let stacked = size.stacked.filter(|&(width, lines)| fits(width, lines, 1));
+        let (width, lines) = stacked.unwrap_or((size.width, options.world.len().max(1) as u16));
+        let scale = (1..=options.scale())
+            .rev()
+            .find(|&scale| fits(width, lines, scale))
+            .unwrap_or(1);
+        let line_height = options.font.height() * scale;
+        let clock_height = match options.mode {
+            // The face fills the rest of the screen
+            Mode::Analog => rows.saturating_sub(above_height + below_height),
+            _ => lines * line_height,
+        };
+        let height = above_height + clock_height + below_height;
+        let block_width = (width * usize::from(scale)).max(size.lines) + border_width;
+        let centre_col = (columns / 2).saturating_sub(block_width as u16 / 2);
+        let centre_row = (rows / 2).saturating_sub(height / 2);
+        let room = (
+            centre_col.min(columns.saturating_sub(centre_col + block_width as u16)) as i16,
+            centre_row.min(rows.saturating_sub(centre_row + height)) as i16,
+        );
+        let offset = (
+            offset.0.clamp(-room.0, room.0),
+            offset.1.clamp(-room.1, room.1),
+        );
+        let top_row = match options.row {
+            Anchor::Center => centre_row.saturating_add_signed(offset.1),
+            Anchor::Absolute(row) => row.min(rows.saturating_sub(height)),
+            Anchor::RelativeFromEnd(row) => rows.saturating_sub(row.saturating_add(height)),
+        };
+        let mut next_row = top_row;
+        let [border_top, title_above_row, phase_row] = above.map(|shown| {
+            shown.then(|| {
+                next_row += 1;
+                next_row - 1
+            })
+        });
+        let time_row = top_row + above_height;
+        // Laps are listed below the rest of the clock, leaving a blank line between them
+        let lap_rows = match options.mode {
+            Mode::Stopwatch => Some((top_row + height + 1).min(rows)..rows),
+            _ => None,
+        };
+        next_row = time_row + clock_height;
+        let [bar_row, hour_bar_row, title_below_row, date_row, week_row, day_row, border_bottom] =
+            below.map(|shown| {
+                shown.then(|| {
+                    next_row += 1;
+                    next_row - 1
+                })
+            });
+        let title_row = title_above_row.or(title_below_row);
+        // The ring fills the screen, so every corner of the clock must be inside it
+        let ring = options.seconds_ring && {
+            let (radius_col, radius_row) = ring_radii(columns, rows);
+            let corner_col = f64::from(block_width as u16 / 2 + 2) / radius_col;
+            let corner_row = f64::from(height / 2 + 1) / radius_row;
+            corner_col.powi(2) + corner_row.powi(2) <= 1.
+        };
+        let border = border_top.zip(border_bottom).map(|(top, bottom)| {
+            let inside = (width * usize::from(scale)).max(size.lines);
+            (
+                top,
+                bottom,
+                inside.min(usize::from(columns).saturating_sub(4)) + 2,
+            )
+        });
+        Layout {
+            columns,
+            rows,
+            col: options.col,
+            title_row,
+            phase_row,
+            lines: size.lines,
+            time_row,
+            clock_height,
+            line_height,
+            vertical: stacked.is_some(),
+            scale,
+            bar_row,
+            hour_bar_row,
+            date_row,
+            week_row,
+            day_row,
+            lap_rows,
+            border,
+            ring,
+            offset,
+            room,
+        }
+    }
+
+    /// The last row of the time, progress bars, date, week, day of the year, and border.
+    pub fn bottom_row(&self) -> u16 {
+        self.border
+            .map(|(_, bottom, _)| bottom)
+            .or(self.day_row)
+            .or(self.week_row)
+            .or(self.date_row)
+            .or(self.hour_bar_row)
+            .or(self.bar_row)
+            .unwrap_or((self.time_row + self.clock_height).saturating_sub(1))
+    }
+
+    /// The first and last rows of everything drawn, from the title or border to the
+    /// bottom row.
+    pub fn rows(&self) -> RangeInclusive<u16> {
+        let rows = [
+            self.title_row,
+            self.phase_row,
+            Some(self.time_row),
+            Some(self.bottom_row()),
+        ];
+        let rows = rows.iter().flatten();
+        *rows.clone().min().unwrap()..=*rows.max().unwrap()
+    }
+
+    /// The column a line `width` columns wide starts at.
+    pub fn column(&self, width: usize) -> u16 {
+        let width = width as u16;
+        match self.col {
+            Anchor::Center => (self.columns / 2)
+                .saturating_sub(width / 2)
+                .saturating_add_signed(self.offset.0)
+                .min(self.columns.saturating_sub(width)),
+            Anchor::Absolute(col) => col.min(self.columns.saturating_sub(width)),
+            Anchor::RelativeFromEnd(col) => self.columns.saturating_sub(col.saturating_add(width)),
+        }
+    }
+
+    /// The first row of each line of the world clock, one per time zone.
+    pub fn world_rows(&self) -> impl Iterator<Item = u16> {
+        (self.time_row..self.time_row + self.clock_height).step_by(usize::from(self.line_height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::parse_pomodoro;
+    use crate::timer::PomodoroCfg;
+
+    #[test]
+    fn layout_from_the_end_past_the_start() {
+        let options = Options {
+            row: Anchor::RelativeFromEnd(u16::MAX),
+            col: Anchor::RelativeFromEnd(u16::MAX),
+            ..Options::default()
+        };
+        let size = ClockSize {
+            width: 8,
+            stacked: None,
+            lines: 0,
+        };
+        let layout = Layout::new(&options, 80, 24, &size, (0, 0));
+        assert_eq!(layout.time_row, 0);
+        assert_eq!(layout.column(8), 0);
+    }
+
+    #[test]
+    fn interval_label_width() {
+        let interval = |rounds| Options {
+            mode: Mode::Pomodoro(PomodoroCfg {
+                interval: true,
+                rounds,
+                ..parse_pomodoro("40s/20s").unwrap()
+            }),
+            ..Options::default()
+        };
+        assert_eq!(
+            widest_line(&interval(Some(20)), 0),
+            "ROUND 20/20 REST (PAUSED)".len()
+        );
+        assert_eq!(
+            widest_line(&interval(Some(20)), 19),
+            "ROUND 20/20 REST (PAUSED)".len()
+        );
+        // Without a limit there's room for the round being worked or rested
+        assert_eq!(
+            widest_line(&interval(None), 0),
+            "ROUND 1 REST (PAUSED)".len()
+        );
+        assert_eq!(
+            widest_line(&interval(None), 9),
+            "ROUND 10 REST (PAUSED)".len()
+        );
+        let pomodoro = Options {
+            mode: Mode::Pomodoro(parse_pomodoro("25m/5m").unwrap()),
+            ..Options::default()
+        };
+        assert_eq!(widest_line(&pomodoro, 0), "LONG BREAK (PAUSED)".len());
+    }
+}
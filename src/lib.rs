@@ -0,0 +1,87 @@
+//! Rendering text with the Unicode seven-segment digits used by `7clock`, along with the
+//! multi-row fonts it can draw the time with.
+
+mod font;
+
+pub use crate::font::{
+    binary_legend, segmentify_ascii, segmentify_big, segmentify_binary, segmentify_braille,
+    segmentify_large,
+};
+
+/// Replace the digits in `s` with the seven-segment digits from the Symbols for Legacy
+/// Computing block (U+1FBF0 to U+1FBF9), other characters are left as they are.
+///
+/// Returns the converted string and its length in characters, which is the number of
+/// terminal columns it occupies.
+pub fn segmentify(s: &str) -> (String, usize) {
+    (
+        s.chars()
+            .map(|ch| {
+                if ch.is_ascii_digit() {
+                    std::char::from_u32(0x1FBC0 + ch as u32).unwrap()
+                } else {
+                    ch
+                }
+            })
+            .collect::<String>(),
+        segmentify_width(s),
+    )
+}
+
+/// The number of terminal columns `s` occupies once converted by [`segmentify`], without
+/// converting it. Each seven-segment digit is one column wide, like the digit it replaces,
+/// so this is the number of characters.
+pub fn segmentify_width(s: &str) -> usize {
+    s.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segmentify_digits() {
+        for (digit, expected) in ('0'..='9').zip('\u{1FBF0}'..='\u{1FBF9}') {
+            let (text, len) = segmentify(&digit.to_string());
+            assert_eq!(text, expected.to_string());
+            assert_eq!(len, 1);
+        }
+    }
+
+    #[test]
+    fn segmented_digits_are_one_column() {
+        use unicode_width::UnicodeWidthChar;
+
+        for (digit, segmented) in ('0'..='9').zip('\u{1FBF0}'..='\u{1FBF9}') {
+            assert_eq!(segmented.width(), Some(1), "U+{:X}", u32::from(segmented));
+            assert_eq!(segmentify_width(&digit.to_string()), 1);
+        }
+    }
+
+    #[test]
+    fn segmentify_passes_other_characters_through() {
+        assert_eq!(segmentify(" "), (" ".to_string(), 1));
+        assert_eq!(segmentify(":"), (":".to_string(), 1));
+        assert_eq!(segmentify("AM"), ("AM".to_string(), 2));
+        assert_eq!(segmentify("PM"), ("PM".to_string(), 2));
+    }
+
+    #[test]
+    fn segmentify_width_matches_segmentify() {
+        for s in ["0123456789", "AM pm", "12:30:45.6", "1-2/3 4", ""] {
+            assert_eq!(
+                segmentify_width(s),
+                segmentify(s).0.chars().count(),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn segmentify_time() {
+        assert_eq!(
+            segmentify("12:30 PM"),
+            ("\u{1FBF1}\u{1FBF2}:\u{1FBF3}\u{1FBF0} PM".to_string(), 8)
+        );
+    }
+}
@@ -1,27 +1,386 @@
+use std::collections::hash_map::RandomState;
 use std::fmt::{Display, Formatter};
-use std::io::{stdout, Stdout, Write};
-use std::process::ExitCode;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, stdout, Stdout, Write};
+use std::mem;
+use std::process::{Child, Command, ExitCode, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crossterm::cursor::{MoveToColumn, MoveToRow};
-use crossterm::event::{poll, Event, KeyCode};
-use crossterm::style::{Color, SetForegroundColor};
+use crossterm::cursor::MoveTo;
+use crossterm::event::{poll, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen,
 };
-use crossterm::{cursor, event, execute, style::Print, terminal, ErrorKind};
-use time::{format_description::FormatItem, macros::format_description, OffsetDateTime};
+use crossterm::{cursor, event, execute, queue, style::Print, terminal, ErrorKind};
+use seven_clock::{
+    binary_legend, segmentify, segmentify_ascii, segmentify_big, segmentify_binary,
+    segmentify_braille, segmentify_large,
+};
+#[cfg(unix)]
+use signal_hook::consts::{SIGCONT, SIGSTOP, SIGTSTP, SIGWINCH};
+use signal_hook::consts::{SIGINT, SIGTERM};
+use time::format_description::well_known::Rfc3339;
+use time::format_description::{self, Component, FormatItem};
+use time::{macros::format_description, Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+use crate::analog::{Cell, Dial};
+use crate::args::{parse_args, usage};
+use crate::colour::{cycle_color, darken, hour_colour, rotate_hue, to_ansi};
+use crate::layout::{clock_size, widest_line, Anchor, Layout};
+use crate::render::{
+    clock_text, draw_border, init_screen, lines_width, render_analog, render_chess,
+    render_countdown, render_date, render_day_of_year, render_epoch, render_hex, render_laps,
+    render_pomodoro, render_progress_bar, render_seconds_ring, render_since, render_stopwatch,
+    render_styled, render_time, render_title, render_uptime, render_week, render_world, scale,
+    time_lines, FrameBuffer, RenderStats,
+};
+use crate::timer::{Chess, ChessCfg, Phase, Pomodoro, PomodoroCfg, Stopwatch};
+use crate::tz::TimeZone;
 
-const TWELVE_HOUR_HMS: &[FormatItem] =
-    format_description!("[hour repr:12 padding:none]:[minute]:[second] [period]");
-const TWELVE_HOUR_HM: &[FormatItem] =
-    format_description!("[hour repr:12 padding:none]:[minute] [period]");
-const TWENTY_FOUR_HOUR_HMS: &[FormatItem] = format_description!("[hour]:[minute]:[second]");
-const TWENTY_FOUR_HOUR_HM: &[FormatItem] = format_description!("[hour]:[minute]");
+mod analog;
+mod args;
+mod colour;
+mod config;
+mod layout;
+mod render;
+mod timer;
+mod tz;
 
+// The built-in time formats are assembled from these parts
+const TWELVE_HOUR: &[FormatItem] = format_description!("[hour repr:12 padding:none]");
+const TWELVE_HOUR_PADDED: &[FormatItem] = format_description!("[hour repr:12]");
+const TWENTY_FOUR_HOUR: &[FormatItem] = format_description!("[hour]");
+const MINUTES: &[FormatItem] = format_description!(":[minute]");
+const SECONDS: &[FormatItem] = format_description!(":[second]");
+const TENTHS: &[FormatItem] = format_description!(".[subsecond digits:1]");
+const PERIOD: &[FormatItem] = format_description!(" [period]");
+const PERIOD_LOWER: &[FormatItem] = format_description!(" [period case:lower]");
+const DATE: &[FormatItem] = format_description!("[weekday repr:short] [year]-[month]-[day]");
+const FLASH_INTERVAL: Duration = Duration::from_millis(250);
+const RAINBOW_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the clock changes colour and rings the bell while an alarm is going off.
+const ALARM_FLASH_INTERVAL: Duration = Duration::from_millis(200);
+const ALARM_BELL_INTERVAL: Duration = Duration::from_secs(1);
+/// How often the clock changes colour while flashing at the start of a minute, three times
+/// to white and back.
+const MINUTE_FLASH_INTERVAL: Duration = Duration::from_millis(100);
+const MINUTE_FLASH_DURATION: Duration = Duration::from_millis(600);
+/// The furthest the clock is moved in each direction by `--jitter`.
+const JITTER: i16 = 2;
+/// How often the clock moves a cell with `--screensaver`.
+const DRIFT_INTERVAL: Duration = Duration::from_secs(3);
+/// How long `--benchmark` renders the clock for.
+const BENCHMARK_DURATION: Duration = Duration::from_secs(5);
+/// Seconds taken to cycle through every colour in rainbow mode.
+const DEFAULT_CYCLE_SPEED: f64 = 10.;
+
+#[derive(Default)]
 struct Options {
     twenty_four_hour: bool,
+    pad_hour: bool,
+    no_period: bool,
+    /// Show am/pm instead of AM/PM.
+    lowercase_period: bool,
+    period_style: Style,
     show_seconds: bool,
+    show_tenths: bool,
+    /// Show the progress through the minute as a bar below the time.
+    seconds_bar: bool,
+    /// Like `seconds_bar` but across the whole terminal, moving a second at a time.
+    progress_bar: bool,
+    /// Show the progress through the hour as a bar below the time.
+    hour_progress: bool,
+    /// Show the seconds as a ring of dots around the clock.
+    seconds_ring: bool,
+    /// The colour of the seconds, and tenths, of the time.
+    seconds_colour: Option<Color>,
+    show_date: bool,
+    /// Show the ISO week number below the time and date.
+    week: bool,
+    /// Show the day of the year below the time, date, and week number.
+    day_of_year: bool,
+    weekday: Option<Side>,
+    blink: Option<Blink>,
+    /// The character shown in place of each colon of the time.
+    separator: Option<char>,
+    /// Stack the hours, minutes, and seconds one per line.
+    vertical: bool,
+    colour: Option<Color>,
+    /// The colour of each line of the world clock, when more than one colour is given.
+    colours: Vec<Color>,
+    gradient: Option<Gradient>,
+    rainbow: bool,
+    /// Turn the hue of `colour` around the colour wheel in rainbow mode, instead of
+    /// going through the colours of the rainbow.
+    cycle_colour: bool,
+    /// Whether the terminal can show RGB colours, otherwise they're drawn with the
+    /// nearest colour of the 256 colour palette.
+    truecolor: bool,
+    /// Change the colour of the clock through the day.
+    colour_by_hour: bool,
+    cycle_speed: Option<f64>,
+    bg_colour: Option<Color>,
+    /// The times of day the clock starts and stops being dimmed, drawn in `night_colour`
+    /// or a darker version of its colour.
+    night: Option<(Time, Time)>,
+    night_colour: Option<Color>,
+    /// The attributes the whole clock is drawn with.
+    styles: Styles,
+    timezone: Option<TimeZone>,
+    utc: bool,
+    no_utc_suffix: bool,
+    /// A fixed UTC offset to show the time at instead of a time zone.
+    offset: Option<UtcOffset>,
+    row: Anchor,
+    col: Anchor,
+    /// The time zones shown by the world clock.
+    world: Vec<TimeZone>,
+    format: Option<String>,
+    date_format: Option<String>,
+    /// Show the date and time in RFC 3339 format.
+    iso: bool,
+    font: Font,
+    scale: Option<u16>,
+    show_off_segments: bool,
+    /// Draw on the main screen instead of the alternate screen.
+    no_alternate_screen: bool,
+    /// Ring the terminal bell and flash the clock at the start of every hour.
+    chime_hourly: bool,
+    /// Briefly flash the clock white at the start of every minute.
+    flash_on_minute: bool,
+    /// Move the clock slowly around the screen, bouncing off the edges.
+    screensaver: bool,
+    /// Move the clock to a random position near the centre every minute.
+    jitter: bool,
+    /// Flash the clock without ringing the bell for the hourly chime.
+    no_bell: bool,
+    /// Keep flashing when the countdown finishes until a key is pressed.
+    hold: bool,
+    /// Count up past the target of `--until` instead of quitting.
+    overtime: bool,
+    /// The time of day to quit at.
+    exit_at: Option<Time>,
+    /// The times of day to ring the bell and flash the clock at.
+    alarms: Vec<Time>,
+    /// A shell command run when the countdown finishes or an alarm goes off.
+    on_finish: Option<String>,
+    /// A label shown above the clock.
+    title: Option<String>,
+    /// Whether the title is above or below the time.
+    title_side: Side,
+    title_colour: Option<Color>,
+    /// Draw a box around the clock.
+    border: bool,
+    border_colour: Option<Color>,
+    /// Print the time and exit instead of running the clock.
+    print: bool,
+    /// Print the time as JSON each time it changes instead of running the clock.
+    json: bool,
+    /// Show how long each frame takes to render on the bottom row.
+    debug: bool,
+    /// Measure how quickly the clock renders instead of running it.
+    benchmark: bool,
+    mode: Mode,
+}
+
+/// How part of the clock is drawn differently to the rest of it.
+#[derive(Clone, Copy, Default)]
+struct Style {
     colour: Option<Color>,
+    dim: bool,
+}
+
+/// The text attributes of the whole clock.
+#[derive(Clone, Copy, Default)]
+struct Styles {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Styles {
+    /// Set the attributes that are turned on.
+    fn apply<S: Write>(&self, screen: &mut S) -> Result<(), Error> {
+        for (on, attribute) in [
+            (self.bold, Attribute::Bold),
+            (self.dim, Attribute::Dim),
+            (self.italic, Attribute::Italic),
+            (self.underline, Attribute::Underlined),
+        ] {
+            if on {
+                queue!(screen, SetAttribute(attribute))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which side of the time an annotation is placed.
+#[derive(Clone, Copy, Default)]
+enum Side {
+    #[default]
+    Before,
+    After,
+}
+
+/// How the colour of the time changes across its columns.
+#[derive(Clone, Copy)]
+enum Gradient {
+    /// Fade from the first colour on the left to the second on the right.
+    Between(Color, Color),
+    /// Go through the hues of the rainbow from red on the left to violet on the right.
+    Rainbow,
+}
+
+/// How the colon separator blinks.
+#[derive(Clone, Copy, PartialEq)]
+enum Blink {
+    /// Hidden for the second half of every second, only when seconds aren't shown.
+    HalfSecond,
+    /// Hidden on odd seconds.
+    OddSeconds,
+}
+
+/// How the digits of the time are drawn.
+#[derive(Clone, Copy, Default)]
+enum Font {
+    /// One row of seven-segment characters.
+    #[default]
+    Segment,
+    /// One row of ordinary digits.
+    Plain,
+    /// Three rows of box-drawing characters.
+    Large,
+    /// Five rows of block characters.
+    Big,
+    /// Three rows of ASCII characters.
+    Ascii,
+    /// Two rows of Braille patterns.
+    Braille,
+    /// Columns of dots showing the bits of each digit, optionally with a row labelling
+    /// the hour, minute, and second columns.
+    Binary { legend: bool },
+}
+
+/// What the clock displays.
+#[derive(Default)]
+enum Mode {
+    /// The time of day.
+    #[default]
+    Clock,
+    /// The time remaining of a countdown of the given duration.
+    Countdown(Duration),
+    /// The time elapsed since starting.
+    Stopwatch,
+    /// Alternating work and break countdowns.
+    Pomodoro(PomodoroCfg),
+    /// Two countdowns side by side, one running at a time.
+    Chess(ChessCfg),
+    /// The time elapsed since the system booted at the given instant.
+    Uptime(Instant),
+    /// The time elapsed since the given moment.
+    Since(Moment),
+    /// The time remaining until the given moment.
+    Until(Moment),
+    /// The number of seconds since the Unix epoch.
+    Epoch,
+    /// The time of day with each of the hours, minutes, and seconds in hexadecimal.
+    Hex,
+    /// The time of day on an analog clock face.
+    Analog,
+}
+
+/// The running counterpart of `Mode`.
+enum Timer {
+    Clock,
+    /// Counting down to the deadline.
+    Countdown(Instant),
+    Stopwatch(Stopwatch),
+    Pomodoro(Pomodoro),
+    Chess(Chess),
+    /// Counting up from when the system booted.
+    Uptime(Instant),
+    /// Counting up from the moment given.
+    Since(OffsetDateTime),
+    /// Counting down to the target, and past it once `passed` with `--overtime`.
+    Until {
+        target: OffsetDateTime,
+        passed: bool,
+    },
+    Epoch,
+    Hex,
+    Analog(Analog),
+}
+
+/// A date and time given on the command line.
+#[derive(Clone, Copy)]
+enum Moment {
+    /// A date and time with its UTC offset.
+    Offset(OffsetDateTime),
+    /// A date and time in the clock's time zone.
+    Local(PrimitiveDateTime),
+}
+
+/// The analog clock face and what is drawn on it.
+struct Analog {
+    dial: Dial,
+    /// The marks around the face, empty until the face has been drawn.
+    face: Vec<Cell>,
+    /// The cells of the hands on screen.
+    hands: Vec<Cell>,
+}
+
+/// The clock while it's running.
+struct State<'a> {
+    options: &'a Options,
+    format: Vec<FormatItem<'a>>,
+    date_format: Vec<FormatItem<'a>>,
+    layout: Layout,
+    /// The date on screen, used to avoid redrawing the date line when it hasn't changed.
+    last_date: Option<Date>,
+    /// The hour and minute shown by the hour progress bar on screen.
+    last_minute: Option<(u8, u8)>,
+    /// The number of filled dots of the ring of seconds on screen.
+    last_second: Option<u8>,
+    /// The lines of the time on screen.
+    frame: FrameBuffer,
+    /// The render times shown with `--debug`.
+    stats: RenderStats,
+    /// Whether the title is on screen.
+    title_drawn: bool,
+    /// The number of laps and scroll position of the lap list on screen.
+    last_laps: Option<(usize, usize)>,
+    /// The phase, count, and whether the Pomodoro timer is paused on screen.
+    last_pomodoro: Option<(Phase, u32, bool)>,
+    /// The date and hour when the hourly chime last checked the time.
+    last_hour: Option<(Date, u8)>,
+    /// The hour and minute when the minute flash last checked the time.
+    flash_minute: Option<(u8, u8)>,
+    /// When the clock started flashing for the start of a minute.
+    minute_flash: Option<Instant>,
+    /// The direction the clock is moving in with `--screensaver`, one column and one row
+    /// at a time, and when it last moved.
+    drift: Option<((i16, i16), Instant)>,
+    /// The hour and minute when `--jitter` last moved the clock.
+    jitter_minute: Option<(u8, u8)>,
+    /// When the clock quits by itself.
+    exit_at: Option<OffsetDateTime>,
+    /// When each alarm that hasn't gone off yet is due.
+    alarms: Vec<OffsetDateTime>,
+    /// When the alarm that is going off started.
+    alarm_started: Option<Instant>,
+    /// When the bell last rang for the alarm that is going off.
+    alarm_bell: Option<Instant>,
+    /// The commands started by `--on-finish`.
+    commands: Vec<io::Result<Child>>,
+    /// The foreground colour currently set.
+    last_colour: Option<Color>,
+    timer: Timer,
+    started: Instant,
 }
 
 #[derive(Debug)]
@@ -54,224 +413,1302 @@ fn main() -> ExitCode {
 
 fn try_main() -> Result<(), Error> {
     let options = parse_args()?;
+    if options.print {
+        return print_time(&options);
+    }
+    if options.json {
+        return print_json(&options);
+    }
+    if options.benchmark {
+        return benchmark(&options);
+    }
+
+    if options.vertical {
+        let (columns, rows) = terminal::size()?;
+        let size = clock_size(&options, &options.format()?);
+        if !Layout::new(&options, columns, rows, &size, (0, 0)).vertical {
+            eprintln!(
+                "warning: the terminal is too short for --vertical, showing the time on one line"
+            );
+        }
+    }
 
     enable_raw_mode()?;
 
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    main_loop(&options)?;
-    execute!(stdout, LeaveAlternateScreen)?;
+    if !options.no_alternate_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    // Restore the terminal even if the clock fails, so the error can be seen
+    let result = main_loop(&options);
+    if !options.no_alternate_screen {
+        execute!(stdout, LeaveAlternateScreen)?;
+    }
 
     disable_raw_mode()?;
 
-    Ok(())
+    let ended = result?;
+    if let Some(summary) = ended.summary {
+        println!("{}", summary);
+    }
+    wait_for_commands(ended.commands)
+}
+
+/// What's left to do once the clock has been quit and the terminal restored.
+struct Ended {
+    /// Printed to stdout, like the elapsed time of the stopwatch.
+    summary: Option<String>,
+    /// The commands started by `--on-finish`, some of which may still be running.
+    commands: Vec<io::Result<Child>>,
 }
 
-fn main_loop(options: &Options) -> Result<(), Error> {
+/// Run the clock until it's quit.
+fn main_loop(options: &Options) -> Result<Ended, Error> {
+    // Quit like q was pressed when terminated, so that the terminal is restored
+    let terminated = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        signal_hook::flag::register(signal, Arc::clone(&terminated))?;
+    }
+    // Restore the terminal before being suspended, and set it up again after
+    #[cfg(unix)]
+    let (suspended, continued) = {
+        let suspended = Arc::new(AtomicBool::new(false));
+        let continued = Arc::new(AtomicBool::new(false));
+        signal_hook::flag::register(SIGTSTP, Arc::clone(&suspended))?;
+        signal_hook::flag::register(SIGCONT, Arc::clone(&continued))?;
+        // `poll` carries on waiting when it's interrupted by a signal, but wakes up for
+        // SIGWINCH, so raising it suspends the clock straight away rather than when the
+        // next update is due. The resize it's read as redraws the clock once, harmlessly.
+        // SAFETY: raise is async-signal-safe and nothing else is done in the handler
+        unsafe {
+            signal_hook::low_level::register(SIGTSTP, || {
+                let _ = signal_hook::low_level::raise(SIGWINCH);
+            })?;
+        }
+        (suspended, continued)
+    };
     let mut stdout = stdout();
-    let (mut columns, mut rows) = terminal::size()?;
-    let format = options.format();
+    let (columns, rows) = terminal::size()?;
+    let mut state = State::new(options, columns, rows)?;
 
     // Clear the screen, move to middle row, and do the initial render
-    init_screen(&mut stdout, columns, rows, options.colour)?;
-    render_time(&mut stdout, format, columns)?;
+    init_screen(&mut stdout, &state.layout, options)?;
+    state.render(&mut stdout)?;
 
-    loop {
-        // Wait up to 1s for another event
-        if poll(options.poll_interval())? {
+    while !terminated.load(Ordering::Relaxed) {
+        #[cfg(unix)]
+        if suspended.swap(false, Ordering::Relaxed) {
+            suspend(&mut stdout, &mut state)?;
+            continued.store(false, Ordering::Relaxed);
+        } else if continued.swap(false, Ordering::Relaxed) {
+            // Stopped by something other than SIGTSTP, which may have changed the terminal
+            resume(&mut stdout, &mut state)?;
+        }
+        // Wait until the next update is due for another event
+        if poll(state.poll_interval())? {
             // It's guaranteed that read() won't block if `poll` returns `Ok(true)`
             match event::read()? {
                 Event::Resize(new_cols, new_rows) => {
-                    columns = new_cols;
-                    rows = new_rows;
-                    init_screen(&mut stdout, columns, rows, options.colour)?;
-                    render_time(&mut stdout, format, columns)?;
+                    state.resize(new_cols, new_rows);
+                    init_screen(&mut stdout, &state.layout, options)?;
+                    state.render(&mut stdout)?;
                 }
+                // Ctrl-C doesn't send SIGINT in raw mode
                 Event::Key(key_event)
                     if key_event == KeyCode::Esc.into()
-                        || key_event == KeyCode::Char('q').into() =>
+                        || key_event == KeyCode::Char('q').into()
+                        || key_event
+                            == KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL) =>
                 {
                     break;
                 }
+                // Neither does Ctrl-Z send SIGTSTP
+                #[cfg(unix)]
+                Event::Key(key_event)
+                    if key_event == KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL) =>
+                {
+                    suspend(&mut stdout, &mut state)?;
+                    continued.store(false, Ordering::Relaxed);
+                }
+                // Any other key stops an alarm that is going off
+                Event::Key(_) if state.dismiss_alarm() => state.render(&mut stdout)?,
+                Event::Key(key_event) if state.handle_key(key_event) => {
+                    state.render(&mut stdout)?;
+                }
                 _ => {}
             }
         } else {
-            // Timeout expired, no event for 1s
-            render_time(&mut stdout, format, columns)?;
+            // Timeout expired, no event before the next update
+            state.render(&mut stdout)?;
+            if state.finished() {
+                state.run_on_finish();
+                if !options.no_bell {
+                    execute!(stdout, Print('\x07'))?;
+                    // The end of the interval timer is marked by a second bell
+                    if matches!(options.mode, Mode::Pomodoro(cfg) if cfg.interval) {
+                        std::thread::sleep(FLASH_INTERVAL);
+                        execute!(stdout, Print('\x07'))?;
+                    }
+                }
+                if options.hold {
+                    flash_until_key(&mut stdout, &mut state)?;
+                } else {
+                    flash(&mut stdout, &mut state, 3)?;
+                }
+                if !options.overtime {
+                    break;
+                }
+            }
+            if state.exit_time_reached() {
+                break;
+            }
+        }
+        // Checked after every render so that it's only missed by an hour passing without
+        // a render, and chimes late rather than not at all if a render is delayed
+        if state.hour_changed() {
+            if !options.no_bell {
+                execute!(stdout, Print('\x07'))?;
+            }
+            flash(&mut stdout, &mut state, 1)?;
+        }
+        if state.drift() || state.jitter() {
+            init_screen(&mut stdout, &state.layout, options)?;
+            state.render(&mut stdout)?;
+        }
+        if state.start_minute_flash() {
+            state.render(&mut stdout)?;
+        }
+        if state.update_alarms() && !options.no_bell {
+            execute!(stdout, Print('\x07'))?;
+        }
+        if state.next_phase() {
+            if !options.no_bell {
+                execute!(stdout, Print('\x07'))?;
+            }
+            if state.fit_phase() {
+                init_screen(&mut stdout, &state.layout, options)?;
+            }
+            state.render(&mut stdout)?;
+        }
+        if state.flag_fell() {
+            if !options.no_bell {
+                execute!(stdout, Print('\x07'))?;
+            }
+            state.render(&mut stdout)?;
         }
     }
 
-    execute!(stdout, cursor::Show, SetForegroundColor(Color::Reset))?;
+    execute!(
+        stdout,
+        cursor::Show,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset),
+        SetAttribute(Attribute::Reset)
+    )?;
+    if options.no_alternate_screen {
+        // Leave the last frame on screen with the cursor on the line below it
+        execute!(stdout, MoveTo(0, state.layout.bottom_row()), Print("\r\n"))?;
+    }
 
-    Ok(())
+    Ok(Ended {
+        summary: state.summary(),
+        commands: mem::take(&mut state.commands),
+    })
 }
 
-fn render_time(stdout: &mut Stdout, format: &[FormatItem], columns: u16) -> Result<(), Error> {
-    let now = OffsetDateTime::now_local().unwrap();
-    let time_str = now.format(format).unwrap();
-    let (time, time_len) = segmentify(&time_str);
-
+/// Restore the terminal and stop the process, like the default action of SIGTSTP. The
+/// clock is set up again and redrawn when the process is continued.
+#[cfg(unix)]
+fn suspend(stdout: &mut Stdout, state: &mut State) -> Result<(), Error> {
     execute!(
         stdout,
-        Clear(ClearType::CurrentLine),
-        MoveToColumn((columns / 2).saturating_sub(time_len as u16 / 2)),
-        Print(time)
+        cursor::Show,
+        SetForegroundColor(Color::Reset),
+        SetBackgroundColor(Color::Reset),
+        SetAttribute(Attribute::Reset)
     )?;
+    if !state.options.no_alternate_screen {
+        execute!(stdout, LeaveAlternateScreen)?;
+    }
+    disable_raw_mode()?;
+    signal_hook::low_level::raise(SIGSTOP)?;
+    resume(stdout, state)
+}
+
+/// Set up the terminal again after the process has been stopped and continued, and
+/// redraw the clock at the size the terminal is now.
+#[cfg(unix)]
+fn resume(stdout: &mut Stdout, state: &mut State) -> Result<(), Error> {
+    enable_raw_mode()?;
+    if !state.options.no_alternate_screen {
+        execute!(stdout, EnterAlternateScreen)?;
+    }
+    let (columns, rows) = terminal::size()?;
+    state.resize(columns, rows);
+    init_screen(stdout, &state.layout, state.options)?;
+    state.redraw(stdout)
+}
+
+/// Flash the display `times` times, e.g. to signal that the countdown has finished.
+fn flash(stdout: &mut Stdout, state: &mut State, times: usize) -> Result<(), Error> {
+    for _ in 0..times {
+        execute!(stdout, Clear(ClearType::All))?;
+        std::thread::sleep(FLASH_INTERVAL);
+        state.redraw(stdout)?;
+        std::thread::sleep(FLASH_INTERVAL);
+    }
     Ok(())
 }
 
-fn init_screen<S: Write>(
-    screen: &mut S,
-    _cols: u16,
-    rows: u16,
-    colour: Option<Color>,
-) -> Result<(), Error> {
-    if let Some(colour) = colour {
-        execute!(
-            screen,
-            Clear(ClearType::All),
-            MoveToRow(rows / 2),
-            cursor::Hide,
-            SetForegroundColor(colour)
-        )?;
-    } else {
-        execute!(
-            screen,
-            Clear(ClearType::All),
-            MoveToRow(rows / 2),
-            cursor::Hide
+/// Flash the display until a key is pressed.
+fn flash_until_key(stdout: &mut Stdout, state: &mut State) -> Result<(), Error> {
+    loop {
+        flash(stdout, state, 1)?;
+        while poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(_) => return Ok(()),
+                Event::Resize(new_cols, new_rows) => {
+                    state.resize(new_cols, new_rows);
+                    init_screen(stdout, &state.layout, state.options)?;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Convert and render the time over and over for a while, then print how quickly it
+/// went to stdout, for `--benchmark`. The frames are thrown away rather than drawn.
+fn benchmark(options: &Options) -> Result<(), Error> {
+    let format = options.format()?;
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let size = clock_size(options, &format);
+    let layout = Layout::new(options, columns, rows, &size, (0, 0));
+    let mut frame = FrameBuffer::default();
+    let (mut iterations, mut min, mut max) = (0u32, Duration::MAX, Duration::ZERO);
+    let started = Instant::now();
+    while started.elapsed() < BENCHMARK_DURATION {
+        let iteration = Instant::now();
+        let now = options.now();
+        segmentify(&now.format(&format).unwrap());
+        render_time(
+            &mut io::sink(),
+            options,
+            &format,
+            now,
+            options.colour,
+            &layout,
+            &mut frame,
         )?;
+        // Draw every frame in full, as if the time had changed
+        frame.clear();
+        let latency = iteration.elapsed();
+        min = min.min(latency);
+        max = max.max(latency);
+        iterations += 1;
     }
+    let elapsed = started.elapsed();
+    println!("{} iterations in {:.2}s", iterations, elapsed.as_secs_f64());
+    println!(
+        "{:.0} iters/sec",
+        f64::from(iterations) / elapsed.as_secs_f64()
+    );
+    println!("{} ns/iter", (elapsed / iterations.max(1)).as_nanos());
+    println!("min: {} ns, max: {} ns", min.as_nanos(), max.as_nanos());
     Ok(())
 }
 
-fn segmentify(s: &str) -> (String, usize) {
-    let mut len = 0;
-    (
-        s.chars()
-            .map(|ch| {
-                len += 1;
-                if ch.is_ascii_digit() {
-                    std::char::from_u32(0x1FBC0 + ch as u32).unwrap()
-                } else {
-                    ch
-                }
-            })
-            .collect::<String>(),
-        len,
-    )
+/// Print the time, and date if it's shown, to stdout and return, for `--print`.
+fn print_time(options: &Options) -> Result<(), Error> {
+    let format = options.format()?;
+    let mut lines = scale(
+        options.font.render(&clock_text(options, &format)),
+        options.scale(),
+    );
+    if let Some(title) = &options.title {
+        match options.title_side {
+            Side::Before => lines.insert(0, title.clone()),
+            Side::After => lines.push(title.clone()),
+        }
+    }
+    if options.show_date {
+        let date_format = options.date_format()?;
+        lines.push(segmentify(&options.now().format(&date_format).unwrap()).0);
+    }
+    let mut stdout = stdout();
+    for line in lines {
+        writeln!(stdout, "{}", line.trim_end())
+            .map_err(|err| Error::Message(format!("unable to print the time: {}", err)))?;
+    }
+    Ok(())
 }
 
-fn parse_args() -> Result<Options, Error> {
-    let mut options = Options::default();
-    let mut args = std::env::args().skip(1);
-    while let Some(arg) = args.next() {
-        match arg.as_str() {
-            "-h" | "--help" => {
-                usage();
-                return Err(Error::ExitCode(ExitCode::SUCCESS));
-            }
-            "-24" => options.twenty_four_hour = true,
-            "-c" | "--color" | "--colour" => {
-                options.colour =
-                    Some(parse_colour(&args.next().ok_or_else(|| {
-                        Error::Usage("--colour requires an argument".into())
-                    })?)?);
-            }
-            "--seconds" => options.show_seconds = true,
-            otherwise => return Err(Error::Usage(format!("unknown option: '{}'", otherwise))),
+/// Print a JSON object describing the time to stdout each time it changes, for use as a
+/// custom module of status bars like waybar and i3bar. Returns when stdout is closed.
+fn print_json(options: &Options) -> Result<(), Error> {
+    let format = options.format()?;
+    let colour = options.colour.map(|colour| {
+        let (r, g, b) = colour::to_rgb(colour);
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    });
+    let mut stdout = stdout();
+    let mut last = None;
+    loop {
+        let time_string = clock_text(options, &format);
+        if last.as_ref() == Some(&time_string) {
+            std::thread::sleep(options.poll_interval(&format));
+            continue;
+        }
+        let unicode_digits = segmentify(&time_string).0;
+        let now = options.now();
+        let raw_time = now
+            .replace_nanosecond(0)
+            .unwrap_or(now)
+            .format(&Rfc3339)
+            .unwrap();
+        let mut fields = vec![
+            ("text", unicode_digits.as_str()),
+            ("tooltip", raw_time.as_str()),
+            ("time_string", time_string.as_str()),
+            ("raw_time", raw_time.as_str()),
+            ("unicode_digits", unicode_digits.as_str()),
+        ];
+        if let Some(colour) = &colour {
+            fields.push(("color", colour));
+        }
+        let object = fields
+            .iter()
+            .map(|(key, value)| format!("{}:{}", json_string(key), json_string(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        match writeln!(stdout, "{{{}}}", object).and_then(|()| stdout.flush()) {
+            Ok(()) => {}
+            // The status bar has gone away
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            Err(err) => return Err(Error::Message(format!("unable to print the time: {}", err))),
         }
+        last = Some(time_string);
+        std::thread::sleep(options.poll_interval(&format));
     }
+}
+
+/// `s` as a quoted JSON string.
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            ch if ch.is_control() => json.push_str(&format!("\\u{:04x}", u32::from(ch))),
+            ch => json.push(ch),
+        }
+    }
+    json.push('"');
+    json
+}
 
-    Ok(options)
+/// The smallest unit of time shown by `format` in milliseconds.
+fn resolution(format: &[FormatItem]) -> u64 {
+    format
+        .iter()
+        .map(|item| match item {
+            FormatItem::Component(Component::Subsecond(_)) => 100,
+            FormatItem::Component(Component::Second(_)) => 1000,
+            FormatItem::Compound(items) | FormatItem::First(items) => resolution(items),
+            FormatItem::Optional(item) => resolution(std::slice::from_ref(*item)),
+            _ => 60_000,
+        })
+        .min()
+        .unwrap_or(60_000)
 }
 
-fn parse_colour(s: &str) -> Result<Color, Error> {
-    if s.starts_with('#') {
-        parse_hex(&s[1..])
+/// Round `duration` up to a whole number of seconds.
+fn round_up(duration: Duration) -> Duration {
+    let secs = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    Duration::from_secs(secs)
+}
+
+/// Format `duration` as MM:SS, or H:MM:SS if it is an hour or longer.
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
     } else {
-        Color::try_from(s).map_err(|()| Error::Message(format!("unable to parse colour: '{}'", s)))
+        format!("{:02}:{:02}", minutes, seconds)
     }
 }
 
-fn parse_hex(hex: &str) -> Result<Color, Error> {
-    if hex.len() != 6 {
-        return Err(Error::Message(format!("invalid colour: '#{}'", hex)));
+/// Format `duration` as the number of days followed by the hours, minutes, and seconds,
+/// e.g. `12d 03:04:05`.
+fn format_days(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "{}d {:02}:{:02}:{:02}",
+        secs / 86_400,
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Format the time left until a target like `format_days`, leaving out the days on the
+/// last day. Once the target has passed the time since it is shown, negated.
+fn format_remaining(remaining: time::Duration) -> String {
+    let (sign, secs) = match Duration::try_from(remaining) {
+        Ok(remaining) => ("", round_up(remaining).as_secs()),
+        // The first second past the target is still shown as zero
+        Err(_) => match (-remaining).whole_seconds().unsigned_abs() {
+            0 => ("", 0),
+            secs => ("-", secs),
+        },
+    };
+    match secs / 86_400 {
+        0 => format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ),
+        _ => format!("{}{}", sign, format_days(Duration::from_secs(secs))),
     }
-    let r = u8::from_str_radix(&hex[0..2], 16).ok();
-    let g = u8::from_str_radix(&hex[2..4], 16).ok();
-    let b = u8::from_str_radix(&hex[4..6], 16).ok();
-    match (r, g, b) {
-        (Some(r), Some(g), Some(b)) => Ok(Color::from((r, g, b))),
-        _ => Err(Error::Message(format!("invalid colour: '#{}'", hex))),
+}
+
+/// A command that runs `command` with the shell.
+fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Wait for the commands started by `--on-finish` to finish, returning an error if any of
+/// them couldn't be run or failed.
+fn wait_for_commands(commands: Vec<io::Result<Child>>) -> Result<(), Error> {
+    let errors = commands
+        .into_iter()
+        .filter_map(|command| match command.and_then(|mut child| child.wait()) {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(format!("--on-finish command failed: {}", status)),
+            Err(err) => Some(format!("unable to run --on-finish command: {}", err)),
+        })
+        .collect::<Vec<_>>();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(Error::Message(errors.join("\n"))),
     }
 }
 
-fn usage() {
-    eprintln!(
-        "{}
+/// Format `duration` like `format_duration` with tenths of a second.
+fn format_tenths(duration: Duration) -> String {
+    format!(
+        "{}.{}",
+        format_duration(duration),
+        duration.subsec_millis() / 100
+    )
+}
+
+/// Whether `time` is from `start` up to `end`, which is the next day if it's earlier than
+/// `start`. A window that starts and ends at the same time is always empty.
+fn in_window(time: Time, (start, end): (Time, Time)) -> bool {
+    match start <= end {
+        true => start <= time && time < end,
+        false => time >= start || time < end,
+    }
+}
+
+/// The first time after `now` that the clock shows `time`, which is tomorrow if `time` has
+/// already passed today.
+fn next_occurrence(now: OffsetDateTime, time: Time) -> OffsetDateTime {
+    let today = now.replace_time(time);
+    if today > now {
+        today
+    } else {
+        today + time::Duration::DAY
+    }
+}
+
+impl Options {
+    fn date_format(&self) -> Result<Vec<FormatItem<'_>>, Error> {
+        match &self.date_format {
+            Some(format) => format_description::parse(format)
+                .map_err(|err| Error::Usage(format!("invalid date format '{}': {}", format, err))),
+            None => Ok(DATE.to_vec()),
+        }
+    }
+
+    fn format(&self) -> Result<Vec<FormatItem<'_>>, Error> {
+        if let Some(format) = &self.format {
+            return format_description::parse(format)
+                .map_err(|err| Error::Usage(format!("invalid format '{}': {}", format, err)));
+        }
+
+        let hour = match (self.twenty_four_hour, self.pad_hour) {
+            (true, _) => TWENTY_FOUR_HOUR,
+            (false, true) => TWELVE_HOUR_PADDED,
+            (false, false) => TWELVE_HOUR,
+        };
+        let mut format = [hour, MINUTES].concat();
+        if self.show_seconds || self.show_tenths {
+            format.extend_from_slice(SECONDS);
+        }
+        if self.show_tenths {
+            format.extend_from_slice(TENTHS);
+        }
+        Ok(format)
+    }
+
+    /// Turn off every option that draws the clock in colour.
+    fn remove_colours(&mut self) {
+        self.colour = None;
+        self.colours.clear();
+        self.gradient = None;
+        self.rainbow = false;
+        self.cycle_colour = false;
+        self.colour_by_hour = false;
+        self.bg_colour = None;
+        self.period_style.colour = None;
+        self.title_colour = None;
+        self.border_colour = None;
+        self.seconds_colour = None;
+        self.night = None;
+        self.night_colour = None;
+        if let Mode::Pomodoro(cfg) = &mut self.mode {
+            cfg.colours = false;
+        }
+    }
 
-{bin} displays a clock using seven-segment characters.
+    /// Change every colour chosen to the nearest colour of the 256 colour palette.
+    fn downsample_colours(&mut self) {
+        for colour in [
+            &mut self.colour,
+            &mut self.bg_colour,
+            &mut self.period_style.colour,
+            &mut self.title_colour,
+            &mut self.border_colour,
+            &mut self.seconds_colour,
+            &mut self.night_colour,
+        ] {
+            *colour = colour.map(to_ansi);
+        }
+        for colour in &mut self.colours {
+            *colour = to_ansi(*colour);
+        }
+        // The colour stays fixed where the hues in between can't be shown
+        if self.cycle_colour {
+            self.rainbow = false;
+            self.cycle_colour = false;
+        }
+    }
 
-USAGE:
-    {bin} [OPTIONS]
+    /// `colour`, or the nearest colour of the 256 colour palette if the terminal can't
+    /// show RGB colours.
+    fn fit(&self, colour: Color) -> Color {
+        if self.truecolor {
+            colour
+        } else {
+            to_ansi(colour)
+        }
+    }
 
-OPTIONS:
-    -h, --help
-            Prints this help information.
+    /// The number of times bigger the clock is drawn, as chosen with `--scale`.
+    fn scale(&self) -> u16 {
+        self.scale.unwrap_or(1)
+    }
 
-    -24
-            Use 24-hour time.
+    fn period(&self) -> &'static [FormatItem<'static>] {
+        if self.lowercase_period {
+            PERIOD_LOWER
+        } else {
+            PERIOD
+        }
+    }
+
+    /// Returns true if AM/PM is shown after the time. It's rendered separately to the rest
+    /// of the time so that it can be styled differently.
+    fn show_period(&self) -> bool {
+        self.format.is_none() && !self.twenty_four_hour && !self.no_period
+    }
+
+    /// The date and time of `moment`, with local times in the clock's time zone.
+    fn moment(&self, moment: Moment) -> OffsetDateTime {
+        match moment {
+            Moment::Offset(moment) => moment,
+            Moment::Local(moment) => match &self.timezone {
+                Some(timezone) => timezone.assume(moment),
+                None if self.utc => moment.assume_utc(),
+                None if self.offset.is_some() => moment.assume_offset(self.now().offset()),
+                None => {
+                    // The offset may differ from now's if daylight saving starts or ends
+                    let guess = moment.assume_offset(self.now().offset());
+                    let offset = UtcOffset::local_offset_at(guess).unwrap_or(guess.offset());
+                    moment.assume_offset(offset)
+                }
+            },
+        }
+    }
 
-    -c, --color, --colour COLOUR
-            Set the colour of the clock.
-            COLOUR can be an RGB hex colour (#RRGGBB) or one of the eight
-            standard colour names: black, red, green, yellow, blue, magenta, cyan,
-            or white.
+    fn now(&self) -> OffsetDateTime {
+        match &self.timezone {
+            Some(timezone) => timezone.now(),
+            None if self.utc => OffsetDateTime::now_utc(),
+            None => match self.offset {
+                Some(offset) => OffsetDateTime::now_utc().to_offset(offset),
+                // UTC is the best that can be done if the local offset is unknown
+                None => OffsetDateTime::now_local().unwrap_or_else(|_| OffsetDateTime::now_utc()),
+            },
+        }
+    }
 
-    --seconds
-            Include seconds.
+    fn blinking(&self, format: &[FormatItem]) -> bool {
+        match self.blink {
+            Some(Blink::HalfSecond) => resolution(format) >= 60_000,
+            Some(Blink::OddSeconds) => true,
+            None => false,
+        }
+    }
 
-AUTHOR
-    Wesley Moore <wes@wezm.net>
+    /// Returns true if the colon should be hidden at `now`.
+    fn blink_phase(&self, format: &[FormatItem], now: OffsetDateTime) -> bool {
+        self.blinking(format)
+            && match self.blink {
+                Some(Blink::HalfSecond) => now.millisecond() >= 500,
+                _ => now.second() % 2 == 1,
+            }
+    }
 
-SEE ALSO
-    https://github.com/wezm/7clock  Source code and issue tracker.",
-        version_string(),
-        bin = "7clock"
-    );
+    fn poll_interval(&self, format: &[FormatItem]) -> Duration {
+        let interval = match resolution(format) {
+            // Update on every tenth of a second so that the digit doesn't skip
+            subsecond if subsecond < 1000 => subsecond,
+            // The ISO date and time is long enough that it only updates once a second
+            1000 if self.iso => 1000,
+            1000 => 500,
+            // Often enough for the seconds bar to move smoothly
+            _ if self.seconds_bar => 250,
+            // The progress bar moves every second even when the seconds aren't shown
+            _ if self.progress_bar || self.seconds_ring => 500,
+            _ if self.blink == Some(Blink::HalfSecond) && self.blinking(format) => 500,
+            _ => 1000,
+        };
+        // Wake on the next interval boundary so that updates stay in phase with the
+        // wall-clock second instead of drifting with the time spent handling events.
+        let millis = u64::from(OffsetDateTime::now_utc().millisecond());
+        Duration::from_millis(interval - millis % interval)
+    }
 }
 
-pub fn version_string() -> String {
-    format!(
-        "{} version {}",
-        env!("CARGO_PKG_NAME"),
-        env!("CARGO_PKG_VERSION")
-    )
+impl<'a> State<'a> {
+    fn new(options: &'a Options, columns: u16, rows: u16) -> Result<Self, Error> {
+        let format = options.format()?;
+        let size = clock_size(options, &format);
+        let layout = Layout::new(options, columns, rows, &size, (0, 0));
+        if layout.scale < options.scale() {
+            return Err(Error::Message(format!(
+                "the clock doesn't fit in the terminal at --scale {}, the largest scale that fits is {}",
+                options.scale(),
+                layout.scale
+            )));
+        }
+        let timer = match options.mode {
+            Mode::Clock => Timer::Clock,
+            Mode::Countdown(duration) => Timer::Countdown(Instant::now() + duration),
+            Mode::Pomodoro(cfg) => Timer::Pomodoro(Pomodoro::new(cfg)),
+            Mode::Chess(cfg) => Timer::Chess(Chess::new(cfg)),
+            Mode::Stopwatch => Timer::Stopwatch(Stopwatch::new()),
+            Mode::Uptime(booted) => Timer::Uptime(booted),
+            Mode::Since(moment) => Timer::Since(options.moment(moment)),
+            Mode::Until(moment) => Timer::Until {
+                target: options.moment(moment),
+                passed: false,
+            },
+            Mode::Epoch => Timer::Epoch,
+            Mode::Hex => Timer::Hex,
+            Mode::Analog => Timer::Analog(Analog::new(&layout)),
+        };
+        Ok(State {
+            options,
+            format,
+            date_format: options.date_format()?,
+            layout,
+            last_date: None,
+            last_minute: None,
+            last_second: None,
+            frame: FrameBuffer::default(),
+            stats: RenderStats::default(),
+            title_drawn: false,
+            last_laps: None,
+            last_pomodoro: None,
+            last_hour: None,
+            flash_minute: None,
+            minute_flash: None,
+            drift: options.screensaver.then(|| ((1, 1), Instant::now())),
+            jitter_minute: None,
+            exit_at: options
+                .exit_at
+                .map(|time| next_occurrence(options.now(), time)),
+            alarms: options
+                .alarms
+                .iter()
+                .map(|&time| next_occurrence(options.now(), time))
+                .collect(),
+            alarm_started: None,
+            alarm_bell: None,
+            commands: Vec::new(),
+            last_colour: options.colour,
+            timer,
+            started: Instant::now(),
+        })
+    }
+
+    fn resize(&mut self, columns: u16, rows: u16) {
+        // Keep the clock as near as it fits to where it was
+        self.relayout(columns, rows, self.layout.offset);
+    }
+
+    /// Move the clock a cell in the direction it's drifting when it's time to, bouncing
+    /// off the edges of the screen. Returns true when it moved and the screen needs to be
+    /// set up again.
+    fn drift(&mut self) -> bool {
+        let (direction, moved) = match &mut self.drift {
+            Some(drift) if drift.1.elapsed() >= DRIFT_INTERVAL => drift,
+            _ => return false,
+        };
+        *moved = Instant::now();
+        let (offset, room) = (self.layout.offset, self.layout.room);
+        if (offset.0 + direction.0).abs() > room.0 {
+            direction.0 = -direction.0;
+        }
+        if (offset.1 + direction.1).abs() > room.1 {
+            direction.1 = -direction.1;
+        }
+        let offset = (offset.0 + direction.0, offset.1 + direction.1);
+        self.relayout(self.layout.columns, self.layout.rows, offset);
+        true
+    }
+
+    /// Move the clock to a random position up to `JITTER` cells from the centre when a new
+    /// minute has started, if `--jitter` was given. Returns true when it moved and the
+    /// screen needs to be set up again. The first call only records the minute.
+    fn jitter(&mut self) -> bool {
+        if !self.options.jitter {
+            return false;
+        }
+        let now = self.options.now();
+        let minute = Some((now.hour(), now.minute()));
+        let started = self.jitter_minute.is_none();
+        if mem::replace(&mut self.jitter_minute, minute) == minute || started {
+            return false;
+        }
+        // The hasher is seeded randomly each time it's created
+        let random = RandomState::new().build_hasher().finish();
+        let cells = (JITTER * 2 + 1) as u64;
+        let offset = (
+            (random % cells) as i16 - JITTER,
+            (random / cells % cells) as i16 - JITTER,
+        );
+        self.relayout(self.layout.columns, self.layout.rows, offset);
+        true
+    }
+
+    /// Lay the clock out again on a screen of `columns` and `rows`, `offset` from the centre.
+    fn relayout(&mut self, columns: u16, rows: u16, offset: (i16, i16)) {
+        let mut size = clock_size(self.options, &self.format);
+        if let Timer::Pomodoro(pomodoro) = &self.timer {
+            size.lines = widest_line(self.options, pomodoro.completed);
+        }
+        self.layout = Layout::new(self.options, columns, rows, &size, offset);
+        if let Timer::Analog(analog) = &mut self.timer {
+            *analog = Analog::new(&self.layout);
+        }
+        // The screen is about to be cleared in the clock's colour by `init_screen`
+        self.last_colour = self.options.colour;
+        self.last_date = None;
+        self.last_minute = None;
+        self.last_second = None;
+        self.frame.clear();
+        self.stats.drawn = None;
+        self.title_drawn = false;
+        self.last_laps = None;
+        self.last_pomodoro = None;
+    }
+
+    /// Handle a key press, returning true if the display needs to be updated.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let stopwatch = match &mut self.timer {
+            Timer::Stopwatch(stopwatch) => stopwatch,
+            Timer::Pomodoro(pomodoro) => {
+                match key.code {
+                    KeyCode::Char(' ') => pomodoro.toggle_pause(),
+                    KeyCode::Char('n') => pomodoro.skip(),
+                    _ => return false,
+                }
+                return true;
+            }
+            Timer::Chess(chess) => {
+                match key.code {
+                    KeyCode::Char(' ') => chess.switch(),
+                    KeyCode::Char('p') => chess.toggle_pause(),
+                    KeyCode::Char('r') => chess.reset(),
+                    _ => return false,
+                }
+                return true;
+            }
+            _ => return false,
+        };
+        match key.code {
+            KeyCode::Char(' ') => stopwatch.toggle_pause(),
+            KeyCode::Char('r') => stopwatch.reset(),
+            KeyCode::Char('l') => stopwatch.lap(),
+            KeyCode::Up => stopwatch.scroll = stopwatch.scroll.saturating_sub(1),
+            KeyCode::Down if stopwatch.scroll + 1 < stopwatch.laps.len() => stopwatch.scroll += 1,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Renders the clock. The date line and lap list are only redrawn when they have
+    /// changed since the last render.
+    fn render(&mut self, stdout: &mut Stdout) -> Result<(), Error> {
+        let started = Instant::now();
+        let now = self.options.now();
+        let colour = self.colour();
+        let recoloured = colour != self.last_colour;
+        if recoloured {
+            queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+            self.last_colour = colour;
+            // Keep the date and title the same colour as the time
+            self.last_date = None;
+            self.last_minute = None;
+            self.last_second = None;
+            self.frame.clear();
+            self.title_drawn = false;
+        }
+        match &mut self.timer {
+            Timer::Clock if !self.options.world.is_empty() => {
+                render_world(stdout, self.options, &self.format, colour, &self.layout)?
+            }
+            Timer::Clock => render_time(
+                stdout,
+                self.options,
+                &self.format,
+                now,
+                colour,
+                &self.layout,
+                &mut self.frame,
+            )?,
+            Timer::Epoch => render_epoch(stdout, self.options, now, colour, &self.layout)?,
+            Timer::Hex => render_hex(stdout, self.options, now, colour, &self.layout)?,
+            Timer::Analog(analog) => {
+                render_analog(stdout, self.options, analog, now, colour, recoloured)?
+            }
+            Timer::Uptime(booted) => {
+                render_uptime(stdout, self.options, *booted, colour, &self.layout)?
+            }
+            Timer::Since(since) => {
+                render_since(stdout, self.options, *since, now, colour, &self.layout)?
+            }
+            Timer::Until { target, .. } => {
+                let spans = [(format_remaining(*target - now), Style::default())];
+                render_styled(
+                    stdout,
+                    self.options,
+                    &spans,
+                    colour,
+                    &self.layout,
+                    self.layout.time_row,
+                )?
+            }
+            Timer::Countdown(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                render_countdown(stdout, self.options, remaining, &self.layout)?
+            }
+            Timer::Pomodoro(pomodoro) => {
+                render_countdown(stdout, self.options, pomodoro.remaining(), &self.layout)?;
+                let shown = Some((
+                    pomodoro.phase,
+                    pomodoro.completed,
+                    pomodoro.paused_at.is_some(),
+                ));
+                if self.last_pomodoro != shown {
+                    render_pomodoro(stdout, pomodoro, &self.layout)?;
+                    self.last_pomodoro = shown;
+                }
+            }
+            Timer::Chess(chess) => render_chess(stdout, self.options, chess, &self.layout)?,
+            Timer::Stopwatch(stopwatch) => {
+                render_stopwatch(stdout, self.options, stopwatch, &self.layout)?;
+                let laps = Some((stopwatch.laps.len(), stopwatch.scroll));
+                if let Some(rows) = self.layout.lap_rows.clone() {
+                    if self.last_laps != laps {
+                        render_laps(stdout, stopwatch, &self.layout, rows)?;
+                        self.last_laps = laps;
+                    }
+                }
+            }
+        }
+        if !self.title_drawn {
+            if let (Some(title), Some(row)) = (&self.options.title, self.layout.title_row) {
+                render_title(stdout, self.options, title, colour, &self.layout, row)?;
+            }
+            self.title_drawn = true;
+        }
+        if let Some(row) = self.layout.hour_bar_row {
+            let minute = Some((now.hour(), now.minute()));
+            if self.last_minute != minute {
+                let lines = time_lines(self.options, &self.format, now, &self.layout);
+                let width = lines_width(self.options, &lines, &self.layout);
+                let progress = f64::from(now.minute()) / 60.;
+                render_progress_bar(stdout, progress, width, &self.layout, row)?;
+                self.last_minute = minute;
+            }
+        }
+        if self.last_date != Some(now.date()) {
+            if let Some(row) = self.layout.date_row {
+                render_date(stdout, &self.date_format, now, &self.layout, row)?;
+            }
+            if let Some(row) = self.layout.week_row {
+                render_week(stdout, now, &self.layout, row)?;
+            }
+            if let Some(row) = self.layout.day_row {
+                render_day_of_year(stdout, now, &self.layout, row)?;
+            }
+            self.last_date = Some(now.date());
+        }
+        if self.layout.ring {
+            render_seconds_ring(stdout, now, &self.layout, self.last_second)?;
+            self.last_second = Some(now.second());
+        }
+        // Lines are cleared across the whole screen when drawn, taking the sides with them
+        if let Some(border) = self.layout.border {
+            draw_border(stdout, self.options, border, colour, &self.layout)?;
+        }
+        // Write the whole frame at once to avoid flicker
+        stdout.flush()?;
+        if self.options.debug {
+            self.stats.record(started.elapsed());
+            // Drawn at most once a second so that drawing them doesn't skew the times
+            if self
+                .stats
+                .drawn
+                .map_or(true, |drawn| drawn.elapsed() >= Duration::from_secs(1))
+            {
+                self.stats.draw(stdout, &self.layout)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders every line of the clock, even those that haven't changed.
+    fn redraw(&mut self, stdout: &mut Stdout) -> Result<(), Error> {
+        self.last_date = None;
+        self.last_minute = None;
+        self.last_second = None;
+        self.frame.clear();
+        self.stats.drawn = None;
+        self.title_drawn = false;
+        self.last_pomodoro = None;
+        self.last_laps = None;
+        if let Timer::Analog(analog) = &mut self.timer {
+            analog.face.clear();
+        }
+        self.render(stdout)
+    }
+
+    /// The colour of the clock, which changes over time in rainbow mode.
+    fn colour(&self) -> Option<Color> {
+        if let Some(started) = self.alarm_started {
+            // Alternate with a contrasting colour while an alarm is going off
+            if started.elapsed().as_millis() / ALARM_FLASH_INTERVAL.as_millis() % 2 == 1 {
+                return match self.options.colour {
+                    Some(Color::Red) => Some(Color::White),
+                    _ => Some(Color::Red),
+                };
+            }
+        }
+        if let Some(started) = self.minute_flash {
+            let elapsed = started.elapsed();
+            if elapsed < MINUTE_FLASH_DURATION
+                && (elapsed.as_millis() / MINUTE_FLASH_INTERVAL.as_millis()) % 2 == 0
+            {
+                return Some(Color::White);
+            }
+        }
+        let colour = match &self.timer {
+            Timer::Pomodoro(pomodoro) if pomodoro.colour().is_some() => pomodoro.colour(),
+            _ if self.options.rainbow => {
+                let speed = self.options.cycle_speed.unwrap_or(DEFAULT_CYCLE_SPEED);
+                let t = self.started.elapsed().as_secs_f64();
+                match self.options.colour {
+                    Some(colour) if self.options.cycle_colour => {
+                        Some(rotate_hue(colour, ((t / speed).fract() * 360.) as f32))
+                    }
+                    _ => Some(cycle_color(t, speed)),
+                }
+            }
+            _ if self.options.colour_by_hour => {
+                let now = self.options.now();
+                Some(hour_colour(now.hour(), now.minute()))
+            }
+            _ => self.options.colour,
+        };
+        let colour = match self.options.night {
+            Some(night) if in_window(self.options.now().time(), night) => Some(
+                self.options
+                    .night_colour
+                    .unwrap_or_else(|| colour.map_or(Color::DarkGrey, darken)),
+            ),
+            _ => colour,
+        };
+        colour.map(|colour| self.options.fit(colour))
+    }
+
+    fn poll_interval(&self) -> Duration {
+        let interval = self.timer_interval();
+        if self.alarm_started.is_some() {
+            interval.min(ALARM_FLASH_INTERVAL)
+        } else if self
+            .minute_flash
+            .is_some_and(|started| started.elapsed() < MINUTE_FLASH_DURATION)
+        {
+            interval.min(MINUTE_FLASH_INTERVAL)
+        } else if self.options.rainbow {
+            interval.min(RAINBOW_INTERVAL)
+        } else if let Some((_, moved)) = self.drift {
+            interval.min(DRIFT_INTERVAL.saturating_sub(moved.elapsed()))
+        } else {
+            interval
+        }
+    }
+
+    /// Start any alarms that are due, returning true when the bell should ring: when an
+    /// alarm goes off and every second until it's dismissed. Each alarm only goes off once.
+    fn update_alarms(&mut self) -> bool {
+        let now = self.options.now();
+        let due = self.alarms.len();
+        self.alarms.retain(|&alarm| alarm > now);
+        if self.alarms.len() != due {
+            self.alarm_started = Some(Instant::now());
+            self.alarm_bell = None;
+            self.run_on_finish();
+        }
+        if self.alarm_started.is_none()
+            || matches!(self.alarm_bell, Some(rang) if rang.elapsed() < ALARM_BELL_INTERVAL)
+        {
+            return false;
+        }
+        self.alarm_bell = Some(Instant::now());
+        true
+    }
+
+    /// Start the `--on-finish` command, if there is one, without waiting for it. Its output
+    /// is discarded so that it doesn't draw over the clock.
+    fn run_on_finish(&mut self) {
+        if let Some(command) = &self.options.on_finish {
+            let child = shell(command)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            self.commands.push(child);
+        }
+    }
+
+    /// Stop the alarm that is going off, returning true if there was one.
+    fn dismiss_alarm(&mut self) -> bool {
+        self.alarm_bell = None;
+        self.alarm_started.take().is_some()
+    }
+
+    /// How long until the time shown next changes.
+    fn timer_interval(&self) -> Duration {
+        match &self.timer {
+            Timer::Clock => self.options.poll_interval(&self.format),
+            // Wake when the displayed second changes
+            Timer::Pomodoro(pomodoro) if pomodoro.paused_at.is_some() => Duration::from_secs(1),
+            Timer::Chess(chess) if chess.flag_fall.is_some() => ALARM_FLASH_INTERVAL,
+            Timer::Chess(chess) if chess.running_since.is_none() => Duration::from_secs(1),
+            Timer::Chess(chess) => match chess.remaining(chess.active).subsec_nanos() {
+                0 => Duration::from_secs(1),
+                nanos => Duration::from_nanos(u64::from(nanos)),
+            },
+            Timer::Countdown(deadline) | Timer::Pomodoro(Pomodoro { deadline, .. }) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match remaining.subsec_nanos() {
+                    0 => Duration::from_secs(1),
+                    nanos => Duration::from_nanos(u64::from(nanos)),
+                }
+            }
+            // Wake when the displayed second changes
+            Timer::Uptime(booted) => {
+                let nanos = booted.elapsed().subsec_nanos();
+                Duration::from_nanos(u64::from(1_000_000_000 - nanos))
+            }
+            // Twice a second, in phase with the wall-clock second
+            Timer::Epoch => {
+                let millis = u64::from(OffsetDateTime::now_utc().millisecond());
+                Duration::from_millis(500 - millis % 500)
+            }
+            // Wake when the next second starts
+            Timer::Hex | Timer::Analog(_) | Timer::Since(_) | Timer::Until { .. } => {
+                let nanos = OffsetDateTime::now_utc().nanosecond();
+                Duration::from_nanos(u64::from(1_000_000_000 - nanos))
+            }
+            Timer::Stopwatch(stopwatch) if stopwatch.paused_at.is_some() => Duration::from_secs(1),
+            // Wake when the displayed tenth of a second changes
+            Timer::Stopwatch(stopwatch) => {
+                let nanos = u64::from(stopwatch.elapsed().subsec_nanos());
+                Duration::from_nanos(100_000_000 - nanos % 100_000_000)
+            }
+        }
+    }
+
+    /// Start the next period of the Pomodoro timer if the current one has ended, returning
+    /// true if it has.
+    fn next_phase(&mut self) -> bool {
+        match &mut self.timer {
+            Timer::Pomodoro(pomodoro) if pomodoro.ended() && !pomodoro.last_round() => {
+                let ended = pomodoro.deadline;
+                pomodoro.next_phase(ended);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Lay the clock out again when the round of the interval timer has outgrown the room
+    /// made for its label, returning true when it has and the screen needs to be set up
+    /// again.
+    fn fit_phase(&mut self) -> bool {
+        match &self.timer {
+            Timer::Pomodoro(pomodoro)
+                if widest_line(self.options, pomodoro.completed) > self.layout.lines => {}
+            _ => return false,
+        }
+        self.relayout(self.layout.columns, self.layout.rows, self.layout.offset);
+        true
+    }
+
+    /// Stop the chess clock if the side to move has run out of time, returning true when
+    /// it does.
+    fn flag_fell(&mut self) -> bool {
+        match &mut self.timer {
+            Timer::Chess(chess) => chess.check_flag(),
+            _ => false,
+        }
+    }
+
+    /// Returns true when a new hour has started since the last call, if the hourly chime
+    /// is on. The first call only records the hour.
+    fn hour_changed(&mut self) -> bool {
+        if !self.options.chime_hourly {
+            return false;
+        }
+        let now = self.options.now();
+        let hour = Some((now.date(), now.hour()));
+        let changed = self.last_hour.is_some() && self.last_hour != hour;
+        self.last_hour = hour;
+        changed
+    }
+
+    /// Start flashing the clock when a new minute has started since the last call, if
+    /// `--flash-on-minute` was given, returning true when it does. The first call only
+    /// records the minute.
+    fn start_minute_flash(&mut self) -> bool {
+        if !self.options.flash_on_minute {
+            return false;
+        }
+        let now = self.options.now();
+        let minute = Some((now.hour(), now.minute()));
+        let changed = self.flash_minute.is_some() && self.flash_minute != minute;
+        self.flash_minute = minute;
+        if changed {
+            self.minute_flash = Some(Instant::now());
+        }
+        changed
+    }
+
+    /// Returns true when the time to quit given with `--exit-at` has been reached.
+    fn exit_time_reached(&self) -> bool {
+        matches!(self.exit_at, Some(exit_at) if self.options.now() >= exit_at)
+    }
+
+    /// The text to print when the clock quits, the elapsed time of the stopwatch or the
+    /// interval timer so that it can be captured.
+    fn summary(&self) -> Option<String> {
+        match &self.timer {
+            Timer::Stopwatch(stopwatch) => Some(format_tenths(stopwatch.elapsed())),
+            Timer::Pomodoro(pomodoro) if pomodoro.cfg.interval => {
+                Some(format_duration(self.started.elapsed()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true when the countdown has reached zero.
+    fn finished(&mut self) -> bool {
+        match &mut self.timer {
+            Timer::Countdown(deadline) => *deadline <= Instant::now(),
+            Timer::Pomodoro(pomodoro) => pomodoro.ended() && pomodoro.last_round(),
+            Timer::Until { target, passed } if !*passed && *target <= self.options.now() => {
+                *passed = true;
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
-impl Options {
-    fn format(&self) -> &[FormatItem] {
-        match (self.twenty_four_hour, self.show_seconds) {
-            (true, true) => TWENTY_FOUR_HOUR_HMS,
-            (true, false) => TWENTY_FOUR_HOUR_HM,
-            (false, true) => TWELVE_HOUR_HMS,
-            (false, false) => TWELVE_HOUR_HM,
+impl Font {
+    /// The number of rows a line of text occupies.
+    fn height(self) -> u16 {
+        match self {
+            Font::Segment | Font::Plain => 1,
+            Font::Large => 3,
+            Font::Big => 5,
+            Font::Ascii => 3,
+            Font::Braille => 2,
+            Font::Binary { legend } => 4 + u16::from(legend),
         }
     }
 
-    fn poll_interval(&self) -> std::time::Duration {
-        let interval = if self.show_seconds { 500 } else { 1000 };
-        std::time::Duration::from_millis(interval)
+    /// Convert `text` into the rows that draw it.
+    fn render(self, text: &str) -> Vec<String> {
+        match self {
+            Font::Segment => vec![segmentify(text).0],
+            Font::Plain => vec![text.to_string()],
+            Font::Large => segmentify_large(text),
+            Font::Big => segmentify_big(text),
+            Font::Ascii => segmentify_ascii(text),
+            Font::Braille => segmentify_braille(text),
+            Font::Binary { legend } => {
+                let mut rows = segmentify_binary(text);
+                if legend {
+                    rows.push(binary_legend(text));
+                }
+                rows
+            }
+        }
     }
 }
 
-impl Default for Options {
-    fn default() -> Self {
-        Options {
-            twenty_four_hour: false,
-            show_seconds: false,
-            colour: None,
+impl Analog {
+    /// The face in the rows `layout` has for the time.
+    fn new(layout: &Layout) -> Self {
+        Analog {
+            dial: Dial::new(layout.columns, layout.time_row, layout.clock_height),
+            face: Vec::new(),
+            hands: Vec::new(),
         }
     }
 }
@@ -294,3 +1731,68 @@ impl From<crossterm::ErrorKind> for Error {
 }
 
 impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::{datetime, time};
+
+    #[test]
+    fn windows() {
+        let day = (time!(9:00), time!(17:00));
+        assert!(in_window(time!(9:00), day));
+        assert!(in_window(time!(12:00), day));
+        assert!(!in_window(time!(17:00), day));
+        assert!(!in_window(time!(8:59:59), day));
+        assert!(!in_window(time!(23:00), day));
+        // Crossing midnight
+        let night = (time!(22:00), time!(7:00));
+        assert!(in_window(time!(22:00), night));
+        assert!(in_window(time!(23:59:59), night));
+        assert!(in_window(time!(0:00), night));
+        assert!(in_window(time!(6:59:59), night));
+        assert!(!in_window(time!(7:00), night));
+        assert!(!in_window(time!(12:00), night));
+        assert!(!in_window(time!(21:59:59), night));
+        // Ending at midnight
+        let evening = (time!(18:00), time!(0:00));
+        assert!(in_window(time!(23:59), evening));
+        assert!(!in_window(time!(0:00), evening));
+        // Starting and ending at the same time
+        let empty = (time!(12:00), time!(12:00));
+        assert!(!in_window(time!(12:00), empty));
+        assert!(!in_window(time!(0:00), empty));
+    }
+
+    #[test]
+    fn next_occurrences() {
+        let now = datetime!(2024-05-14 21:30 +10:00);
+        // Later today
+        assert_eq!(
+            next_occurrence(now, time!(22:00)),
+            datetime!(2024-05-14 22:00 +10:00)
+        );
+        assert_eq!(
+            next_occurrence(now, time!(21:30:01)),
+            datetime!(2024-05-14 21:30:01 +10:00)
+        );
+        // Already passed, or showing now, so tomorrow
+        assert_eq!(
+            next_occurrence(now, time!(21:30)),
+            datetime!(2024-05-15 21:30 +10:00)
+        );
+        assert_eq!(
+            next_occurrence(now, time!(0:00)),
+            datetime!(2024-05-15 0:00 +10:00)
+        );
+        // Into the next month and year
+        assert_eq!(
+            next_occurrence(datetime!(2024-02-29 12:00 UTC), time!(6:00)),
+            datetime!(2024-03-01 6:00 UTC)
+        );
+        assert_eq!(
+            next_occurrence(datetime!(2024-12-31 23:59:59 UTC), time!(0:00)),
+            datetime!(2025-01-01 0:00 UTC)
+        );
+    }
+}
@@ -0,0 +1,1190 @@
+//! Drawing the parts of the clock on the screen.
+
+use std::io::{Stdout, Write};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use crossterm::cursor::{MoveTo, MoveToRow};
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, execute, queue};
+use seven_clock::{segmentify, segmentify_width};
+use time::format_description::{Component, FormatItem};
+use time::util::days_in_year;
+use time::OffsetDateTime;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::layout::Layout;
+use crate::timer::{Chess, Phase, Pomodoro, Stopwatch};
+use crate::{
+    colour, format_days, format_duration, format_remaining, format_tenths, round_up, Analog, Error,
+    Gradient, Mode, Options, Side, Style, ALARM_FLASH_INTERVAL,
+};
+
+const WEEKDAYS: [&str; 7] = ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"];
+
+/// The colour of unlit segments with `--show-off-segments`.
+const OFF_SEGMENT_COLOUR: Color = Color::DarkGrey;
+
+/// How long frames take to render, shown with `--debug`.
+#[derive(Default)]
+pub struct RenderStats {
+    last: Duration,
+    max: Duration,
+    total: Duration,
+    count: u32,
+    /// When the stats were last drawn.
+    pub drawn: Option<Instant>,
+}
+
+/// The lines of the time on screen, so that only the characters that change are drawn
+/// instead of clearing and drawing the whole line, which flickers on slow terminals.
+#[derive(Default)]
+pub struct FrameBuffer {
+    /// The row, first column, and text of each line.
+    lines: Vec<(u16, u16, String)>,
+}
+
+pub fn render_time<W: Write>(
+    stdout: &mut W,
+    options: &Options,
+    format: &[FormatItem],
+    now: OffsetDateTime,
+    colour: Option<Color>,
+    layout: &Layout,
+    frame: &mut FrameBuffer,
+) -> Result<(), Error> {
+    // Anything drawn since the screen was set up may have reset the attributes
+    options.styles.apply(stdout)?;
+    let lines = time_lines(options, format, now, layout);
+    let rows = (layout.time_row..).step_by(usize::from(layout.line_height));
+    // Only lines all in the colour of the clock can be drawn a character at a time
+    let plain = options.gradient.is_none()
+        && !options.show_off_segments
+        && lines
+            .iter()
+            .flatten()
+            .all(|(_, style)| style.colour.is_none() && !style.dim);
+    if !plain {
+        frame.clear();
+    }
+    for (spans, row) in lines.iter().zip(rows) {
+        if plain {
+            let text = spans
+                .iter()
+                .map(|(text, _)| text.as_str())
+                .collect::<String>();
+            let rendered = scale(options.font.render(&text), layout.scale);
+            for (line, row) in rendered.into_iter().zip(row..) {
+                frame.draw(stdout, line, layout, row)?;
+            }
+        } else {
+            render_styled(stdout, options, spans, colour, layout, row)?;
+        }
+    }
+    if let Some(row) = layout.bar_row {
+        let seconds = f64::from(now.second());
+        if options.progress_bar {
+            let width = usize::from(layout.columns);
+            render_progress_bar(stdout, seconds / 60., width, layout, row)?;
+        } else {
+            let seconds = seconds + f64::from(now.nanosecond()) / 1e9;
+            let width = lines_width(options, &lines, layout);
+            render_progress_bar(stdout, seconds / 60., width, layout, row)?;
+        }
+    }
+    Ok(())
+}
+
+/// The spans of each line of the time, one line unless it's stacked.
+pub fn time_lines(
+    options: &Options,
+    format: &[FormatItem],
+    now: OffsetDateTime,
+    layout: &Layout,
+) -> Vec<Vec<(String, Style)>> {
+    if layout.vertical {
+        vertical_spans(options, format, now)
+    } else {
+        vec![time_spans(options, format, now)]
+    }
+}
+
+/// The width on screen of the widest of `lines`.
+pub fn lines_width(options: &Options, lines: &[Vec<(String, Style)>], layout: &Layout) -> usize {
+    lines
+        .iter()
+        .map(|spans| spans_width(options, spans))
+        .max()
+        .unwrap_or(0)
+        * usize::from(layout.scale)
+}
+
+/// Render a bar `width` columns wide that is filled from the left by `progress`, a
+/// fraction between 0 and 1.
+pub fn render_progress_bar<W: Write>(
+    stdout: &mut W,
+    progress: f64,
+    width: usize,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    // The partially filled cell at the end of the bar, in eighths
+    const EIGHTHS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+    let eighths = (progress * (width * 8) as f64) as usize;
+    let bar = format!("{}{}", "█".repeat(eighths / 8), EIGHTHS[eighths % 8]);
+    queue!(
+        stdout,
+        MoveToRow(row),
+        Clear(ClearType::CurrentLine),
+        MoveTo(layout.column(width), row),
+        Print(bar)
+    )?;
+    Ok(())
+}
+
+/// Render one line per time zone of the world clock, each labelled with the zone name.
+pub fn render_world(
+    stdout: &mut Stdout,
+    options: &Options,
+    format: &[FormatItem],
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let lines = options
+        .world
+        .iter()
+        .map(|zone| {
+            (
+                zone_label(zone.name()),
+                time_spans(options, format, zone.now()),
+            )
+        })
+        .collect::<Vec<_>>();
+    let width = |spans: &[(String, Style)]| -> usize {
+        spans.iter().map(|(text, _)| text.chars().count()).sum()
+    };
+    let label_width = lines.iter().map(|(label, _)| label.chars().count()).max();
+    let time_width = lines.iter().map(|(_, spans)| width(spans)).max();
+    // Truncate the labels so that the lines fit on the screen
+    let label_width = label_width
+        .unwrap_or(0)
+        .min(usize::from(layout.columns).saturating_sub(time_width.unwrap_or(0) + 2));
+
+    for (i, ((label, mut spans), row)) in lines.into_iter().zip(layout.world_rows()).enumerate() {
+        let colour = options.colours.get(i).copied().or(colour);
+        if !options.colours.is_empty() {
+            queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+        }
+
+        // Pad the label and time so that every line is the same width, which lines up
+        // the colons
+        let label = label.chars().take(label_width).collect::<String>();
+        let padding = " ".repeat(time_width.unwrap_or(0) - width(&spans));
+        spans.insert(
+            0,
+            (
+                format!("{:<width$}  {}", label, padding, width = label_width),
+                Style::default(),
+            ),
+        );
+        render_styled(stdout, options, &spans, colour, layout, row)?;
+    }
+    if !options.colours.is_empty() {
+        queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+    }
+    Ok(())
+}
+
+/// A short label for the time zone `name`, e.g. New York for America/New_York.
+fn zone_label(name: &str) -> String {
+    name.rsplit('/').next().unwrap_or(name).replace('_', " ")
+}
+
+/// The text of the time, split into spans that are styled differently.
+pub fn time_spans(
+    options: &Options,
+    format: &[FormatItem],
+    now: OffsetDateTime,
+) -> Vec<(String, Style)> {
+    let mut time_str = now.format(format).unwrap();
+    let (colons, seconds_start) = time_colons(format, now);
+    if options.blink_phase(format, now) {
+        // Replace rather than remove the colons so the width stays the same
+        time_str = replace_colons(&time_str, &colons, ' ');
+    } else if let Some(separator) = options.separator {
+        time_str = replace_colons(&time_str, &colons, separator);
+    }
+    let spans = match (options.seconds_colour, seconds_start) {
+        (Some(colour), Some(start)) => {
+            let seconds = time_str.chars().skip(start).collect();
+            let hours_minutes = time_str.chars().take(start).collect();
+            let style = Style {
+                colour: Some(colour),
+                ..Style::default()
+            };
+            vec![(hours_minutes, Style::default()), (seconds, style)]
+        }
+        _ => vec![(time_str, Style::default())],
+    };
+    annotate(options, now, spans)
+}
+
+/// The lines of the time stacked by `--vertical`, one for each of the hours, minutes, and
+/// seconds without the colons between them. AM/PM and the other annotations are on the
+/// last line.
+pub fn vertical_spans(
+    options: &Options,
+    format: &[FormatItem],
+    now: OffsetDateTime,
+) -> Vec<Vec<(String, Style)>> {
+    let time_str = now.format(format).unwrap();
+    let mut lines = time_str
+        .split(':')
+        .enumerate()
+        .map(|(i, part)| {
+            let style = Style {
+                colour: options.seconds_colour.filter(|_| i == 2),
+                ..Style::default()
+            };
+            vec![(part.to_string(), style)]
+        })
+        .collect::<Vec<_>>();
+    if let Some(last) = lines.pop() {
+        lines.push(annotate(options, now, last));
+    }
+    lines
+}
+
+/// Add AM/PM, UTC, and the day of the week around the time in `spans`, as chosen in
+/// `options`.
+fn annotate(
+    options: &Options,
+    now: OffsetDateTime,
+    mut spans: Vec<(String, Style)>,
+) -> Vec<(String, Style)> {
+    if options.show_period() {
+        spans.push((now.format(options.period()).unwrap(), options.period_style));
+    }
+    if options.utc && !options.no_utc_suffix {
+        spans.push((" UTC".to_string(), Style::default()));
+    }
+    if let Some(side) = options.weekday {
+        // The day name is plain text, segmentify only converts the digits of the time
+        let day = WEEKDAYS[usize::from(now.weekday().number_days_from_monday())];
+        match side {
+            Side::Before => spans.insert(0, (format!("{} ", day), Style::default())),
+            Side::After => spans.push((format!(" {}", day), Style::default())),
+        }
+    }
+    spans
+}
+
+/// The character index of each colon between the hours, minutes, and seconds of `now`
+/// formatted with `format`, and the index of the colon before the seconds. Other colons,
+/// like the one in the offset of an ISO 8601 time, aren't included.
+fn time_colons(format: &[FormatItem], now: OffsetDateTime) -> (Vec<usize>, Option<usize>) {
+    let parts = format
+        .iter()
+        .map(|item| {
+            let component = match item {
+                FormatItem::Component(component) => Some(*component),
+                _ => None,
+            };
+            (now.format(item).unwrap_or_default(), component)
+        })
+        .collect::<Vec<_>>();
+    let (mut colons, mut seconds_start) = (Vec::new(), None);
+    let mut start = 0;
+    for (i, (text, component)) in parts.iter().enumerate() {
+        let before = parts[..i]
+            .iter()
+            .rev()
+            .find_map(|(_, component)| *component);
+        let after = parts[i + 1..].iter().find_map(|(_, component)| *component);
+        if component.is_none()
+            && matches!(before, Some(Component::Hour(_) | Component::Minute(_)))
+            && matches!(after, Some(Component::Minute(_) | Component::Second(_)))
+        {
+            for (offset, c) in text.chars().enumerate() {
+                if c == ':' {
+                    colons.push(start + offset);
+                    if matches!(after, Some(Component::Second(_))) {
+                        seconds_start = Some(start + offset);
+                    }
+                }
+            }
+        }
+        start += text.chars().count();
+    }
+    (colons, seconds_start)
+}
+
+/// Replace the characters of `time_str` at the indices in `colons` with `replacement`.
+fn replace_colons(time_str: &str, colons: &[usize], replacement: char) -> String {
+    time_str
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if colons.contains(&i) { replacement } else { c })
+        .collect()
+}
+
+/// Render `spans` centred on `row`, coloured along the gradient if one was chosen.
+pub fn render_styled<W: Write>(
+    stdout: &mut W,
+    options: &Options,
+    spans: &[(String, Style)],
+    colour: Option<Color>,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    if let Some(gradient) = options.gradient {
+        let text = spans
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<String>();
+        return render_gradient(stdout, options, &text, gradient, colour, layout, row);
+    }
+    render_spans(stdout, options, spans, colour, layout, row)
+}
+
+/// Render the time with each column coloured along a gradient.
+fn render_gradient<W: Write>(
+    stdout: &mut W,
+    options: &Options,
+    text: &str,
+    gradient: Gradient,
+    colour: Option<Color>,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let lines = scale(options.font.render(text), layout.scale);
+    let off = off_segments(options, text, layout);
+    for (i, row) in (row..row + layout.line_height).enumerate() {
+        let chars = colour_columns(&lines[i], gradient);
+        queue!(
+            stdout,
+            MoveToRow(row),
+            Clear(ClearType::CurrentLine),
+            MoveTo(layout.column(chars.len()), row),
+        )?;
+        let mut off_chars = off.as_ref().map(|off| off[i].chars());
+        for (ch, colour) in chars {
+            match off_chars.as_mut().and_then(Iterator::next) {
+                Some(off_ch) if ch == ' ' => queue!(
+                    stdout,
+                    SetForegroundColor(OFF_SEGMENT_COLOUR),
+                    Print(off_ch)
+                )?,
+                _ => queue!(stdout, SetForegroundColor(options.fit(colour)), Print(ch))?,
+            }
+        }
+    }
+    queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+    Ok(())
+}
+
+/// Render the Unix timestamp of `now`, which grows wider every so often so it's
+/// re-centred on each render.
+pub fn render_epoch(
+    stdout: &mut Stdout,
+    options: &Options,
+    now: OffsetDateTime,
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_unix(now), Style::default())];
+    render_styled(stdout, options, &spans, colour, layout, layout.time_row)
+}
+
+/// Render the time of `now` in hexadecimal.
+pub fn render_hex(
+    stdout: &mut Stdout,
+    options: &Options,
+    now: OffsetDateTime,
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_hex_time(now), Style::default())];
+    render_styled(stdout, options, &spans, colour, layout, layout.time_row)
+}
+
+/// Render the hands of the analog clock in `colour`, drawing the face first if it isn't
+/// on screen. Only the cells the hands have moved off are erased, unless `recoloured` is
+/// true, in which case the hands are redrawn in full.
+pub fn render_analog(
+    stdout: &mut Stdout,
+    options: &Options,
+    analog: &mut Analog,
+    now: OffsetDateTime,
+    colour: Option<Color>,
+    recoloured: bool,
+) -> Result<(), Error> {
+    if analog.face.is_empty() {
+        analog.face = analog.dial.face();
+        // The screen has been cleared, so there are no hands to erase
+        analog.hands.clear();
+        queue!(stdout, SetForegroundColor(Color::Reset))?;
+        for &(col, row, ch) in &analog.face {
+            queue!(stdout, MoveTo(col, row), Print(ch))?;
+        }
+    }
+    let hands = analog.dial.hands(now, options.show_seconds);
+    if hands == analog.hands && !recoloured {
+        return Ok(());
+    }
+    queue!(stdout, SetForegroundColor(Color::Reset))?;
+    for &(col, row, _) in &analog.hands {
+        if hands.iter().any(|&(c, r, _)| (c, r) == (col, row)) {
+            continue;
+        }
+        // Put back the mark the hand was covering, if any
+        let ch = analog
+            .face
+            .iter()
+            .find(|&&(c, r, _)| (c, r) == (col, row))
+            .map_or(' ', |&(_, _, ch)| ch);
+        queue!(stdout, MoveTo(col, row), Print(ch))?;
+    }
+    queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+    for &(col, row, ch) in &hands {
+        queue!(stdout, MoveTo(col, row), Print(ch))?;
+    }
+    analog.hands = hands;
+    Ok(())
+}
+
+pub fn render_uptime(
+    stdout: &mut Stdout,
+    options: &Options,
+    booted: Instant,
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_uptime(booted.elapsed()), Style::default())];
+    render_styled(stdout, options, &spans, colour, layout, layout.time_row)
+}
+
+pub fn render_since(
+    stdout: &mut Stdout,
+    options: &Options,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_days(elapsed_since(now, since)), Style::default())];
+    render_styled(stdout, options, &spans, colour, layout, layout.time_row)
+}
+
+pub fn render_countdown(
+    stdout: &mut Stdout,
+    options: &Options,
+    remaining: Duration,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_duration(round_up(remaining)), Style::default())];
+    render_spans(stdout, options, &spans, None, layout, layout.time_row)
+}
+
+/// Render the phase of the Pomodoro timer above the countdown, and the number of work
+/// periods finished in the top right corner.
+pub fn render_pomodoro(
+    stdout: &mut Stdout,
+    pomodoro: &Pomodoro,
+    layout: &Layout,
+) -> Result<(), Error> {
+    if let Some(row) = layout.phase_row {
+        let mut label = match pomodoro.phase {
+            Phase::Work => "WORK",
+            Phase::Break => "BREAK",
+            Phase::LongBreak => "LONG BREAK",
+        }
+        .to_string();
+        if pomodoro.cfg.interval {
+            // The round of a rest is the one just finished
+            let round = match pomodoro.phase {
+                Phase::Work => pomodoro.completed + 1,
+                _ => pomodoro.completed,
+            };
+            label = match pomodoro.cfg.rounds {
+                Some(rounds) => format!("ROUND {}/{}", round, rounds),
+                None => format!("ROUND {}", round),
+            };
+            if pomodoro.phase != Phase::Work {
+                label.push_str(" REST");
+            }
+        }
+        if pomodoro.paused_at.is_some() {
+            label.push_str(" (PAUSED)");
+        }
+        render_line(stdout, &label, layout, row)?;
+    }
+    // The interval timer shows the count in its label instead
+    if pomodoro.cfg.interval {
+        return Ok(());
+    }
+    let count = pomodoro.completed.to_string();
+    queue!(
+        stdout,
+        MoveTo(
+            layout
+                .columns
+                .saturating_sub(segmentify_width(&count) as u16),
+            0
+        ),
+        Print(segmentify(&count).0)
+    )?;
+    Ok(())
+}
+
+/// Render each side of the chess clock in the middle of its half of the terminal. The
+/// side to move is drawn in inverse video while its clock runs and both are dimmed while
+/// stopped. A side that has run out of time flashes.
+pub fn render_chess(
+    stdout: &mut Stdout,
+    options: &Options,
+    chess: &Chess,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let half = layout.columns / 2;
+    let sides = [0, 1].map(|side| {
+        let text = format_duration(round_up(chess.remaining(side)));
+        let lines = scale(options.font.render(&text), layout.scale);
+        let width = lines[0].chars().count() as u16;
+        let column = half * side as u16 + (half / 2).saturating_sub(width / 2);
+        let flashed = chess.flag_fall.is_some_and(|(flagged, at)| {
+            flagged == side && at.elapsed().as_millis() / ALARM_FLASH_INTERVAL.as_millis() % 2 == 1
+        });
+        (lines, column, flashed)
+    });
+    let running = chess.running_since.is_some();
+    let (on, off) = match running {
+        true => (Attribute::Reverse, Attribute::NoReverse),
+        false => (Attribute::Dim, Attribute::NormalIntensity),
+    };
+    for (i, row) in (layout.time_row..layout.time_row + layout.line_height).enumerate() {
+        queue!(stdout, MoveToRow(row), Clear(ClearType::CurrentLine))?;
+        for (side, (lines, column, flashed)) in sides.iter().enumerate() {
+            if *flashed {
+                continue;
+            }
+            let highlighted = !running || side == chess.active;
+            queue!(stdout, MoveTo(*column, row))?;
+            if highlighted {
+                queue!(stdout, SetAttribute(on))?;
+            }
+            queue!(stdout, Print(&lines[i]))?;
+            if highlighted {
+                queue!(stdout, SetAttribute(off))?;
+                options.styles.apply(stdout)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn render_stopwatch(
+    stdout: &mut Stdout,
+    options: &Options,
+    stopwatch: &Stopwatch,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let spans = [(format_tenths(stopwatch.elapsed()), Style::default())];
+    render_spans(stdout, options, &spans, None, layout, layout.time_row)
+}
+
+/// Render the list of laps, most recent first, with the time of each lap and the total.
+pub fn render_laps(
+    stdout: &mut Stdout,
+    stopwatch: &Stopwatch,
+    layout: &Layout,
+    rows: Range<u16>,
+) -> Result<(), Error> {
+    let mut laps = stopwatch
+        .laps
+        .iter()
+        .enumerate()
+        .rev()
+        .skip(stopwatch.scroll)
+        .map(|(i, &total)| {
+            let split = total
+                - i.checked_sub(1)
+                    .map_or(Duration::ZERO, |prev| stopwatch.laps[prev]);
+            format!(
+                "LAP {}  {}  {}",
+                i + 1,
+                format_tenths(split),
+                format_tenths(total)
+            )
+        });
+    for row in rows {
+        match laps.next() {
+            Some(lap) => render_line(stdout, &lap, layout, row)?,
+            None => queue!(stdout, MoveToRow(row), Clear(ClearType::CurrentLine))?,
+        }
+    }
+    Ok(())
+}
+
+/// Render the title as it is, without converting it to seven-segment characters. A title
+/// too wide for the terminal is cut short with an ellipsis.
+pub fn render_title(
+    stdout: &mut Stdout,
+    options: &Options,
+    title: &str,
+    colour: Option<Color>,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let title = clip_title(title, usize::from(layout.columns));
+    queue!(
+        stdout,
+        MoveToRow(row),
+        Clear(ClearType::CurrentLine),
+        MoveTo(layout.column(title.width()), row),
+    )?;
+    match options.title_colour {
+        Some(title_colour) => queue!(
+            stdout,
+            SetForegroundColor(title_colour),
+            Print(title),
+            SetForegroundColor(colour.unwrap_or(Color::Reset))
+        )?,
+        None => queue!(stdout, Print(title))?,
+    }
+    Ok(())
+}
+
+/// `title` cut short with an ellipsis if it's wider than `columns`.
+fn clip_title(title: &str, columns: usize) -> String {
+    if title.width() <= columns {
+        return title.to_string();
+    }
+    let mut width = 0;
+    let mut clipped = title
+        .chars()
+        .take_while(|ch| {
+            width += ch.width().unwrap_or(0);
+            width < columns
+        })
+        .collect::<String>();
+    clipped.push('…');
+    clipped
+}
+
+/// Draw a box with rounded corners from the `top` row to the `bottom` row around `width`
+/// columns in the middle.
+pub fn draw_border(
+    stdout: &mut Stdout,
+    options: &Options,
+    (top, bottom, width): (u16, u16, usize),
+    colour: Option<Color>,
+    layout: &Layout,
+) -> Result<(), Error> {
+    let left = layout.column(width).saturating_sub(1);
+    let right = left + width as u16 + 1;
+    let line = "─".repeat(width);
+    if let Some(border_colour) = options.border_colour {
+        queue!(stdout, SetForegroundColor(border_colour))?;
+    }
+    queue!(
+        stdout,
+        MoveTo(left, top),
+        Print(format!("╭{}╮", line)),
+        MoveTo(left, bottom),
+        Print(format!("╰{}╯", line)),
+    )?;
+    for row in top + 1..bottom {
+        queue!(
+            stdout,
+            MoveTo(left, row),
+            Print('│'),
+            MoveTo(right, row),
+            Print('│')
+        )?;
+    }
+    if options.border_colour.is_some() {
+        queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+    }
+    Ok(())
+}
+
+/// The number of columns and rows from the centre of the screen to the ring of seconds.
+pub fn ring_radii(columns: u16, rows: u16) -> (f64, f64) {
+    (
+        f64::from(columns.saturating_sub(1)) / 2.,
+        f64::from(rows.saturating_sub(1)) / 2.,
+    )
+}
+
+/// The column and row of each dot of the ring of seconds, clockwise from the top.
+fn ring_dots(columns: u16, rows: u16) -> impl Iterator<Item = (u16, u16)> {
+    let (radius_col, radius_row) = ring_radii(columns, rows);
+    (0..60).map(move |second| {
+        let angle = f64::from(second) / 60. * std::f64::consts::TAU;
+        (
+            (radius_col + radius_col * angle.sin()).round() as u16,
+            (radius_row - radius_row * angle.cos()).round() as u16,
+        )
+    })
+}
+
+/// Render the ring of dots around the clock, with a dot filled for each second of the
+/// minute that has passed. Only the dots filled since `last`, the number filled on
+/// screen, are drawn, along with those in rows of the clock that clear the whole line.
+pub fn render_seconds_ring(
+    stdout: &mut Stdout,
+    now: OffsetDateTime,
+    layout: &Layout,
+    last: Option<u8>,
+) -> Result<(), Error> {
+    let filled = now.second();
+    let changed = match last {
+        Some(last) if last <= filled => last..filled,
+        // A new minute empties the ring
+        _ => 0..60,
+    };
+    let cleared = layout.rows();
+    for (second, (col, row)) in (0..).zip(ring_dots(layout.columns, layout.rows)) {
+        if changed.contains(&second) || cleared.contains(&row) {
+            let dot = if second < filled { '•' } else { '·' };
+            queue!(stdout, MoveTo(col, row), Print(dot))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn render_date(
+    stdout: &mut Stdout,
+    format: &[FormatItem],
+    now: OffsetDateTime,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let date_str = now.format(format).unwrap();
+    render_line(stdout, &date_str, layout, row)
+}
+
+/// Render the ISO 8601 week number, e.g. W20. It's always two digits so that it doesn't
+/// move when the week number gains a digit.
+pub fn render_week(
+    stdout: &mut Stdout,
+    now: OffsetDateTime,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let week = format!("W{:02}", now.iso_week());
+    render_line(stdout, &week, layout, row)
+}
+
+/// Render the day of the year out of the number of days in the year, e.g. 135/366.
+pub fn render_day_of_year(
+    stdout: &mut Stdout,
+    now: OffsetDateTime,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let day = format!("{:03}/{}", now.ordinal(), days_in_year(now.year()));
+    render_line(stdout, &day, layout, row)
+}
+
+/// Segmentify `text` and queue printing it centred on `row`, replacing whatever was there.
+fn render_line(stdout: &mut Stdout, text: &str, layout: &Layout, row: u16) -> Result<(), Error> {
+    let column = layout.column(segmentify_width(text));
+    queue!(
+        stdout,
+        MoveToRow(row),
+        Clear(ClearType::CurrentLine),
+        MoveTo(column, row),
+        Print(segmentify(text).0)
+    )?;
+    Ok(())
+}
+
+/// Like `render_line` but each span of text is drawn in its own style, using the font
+/// chosen in `options`, from `row` downwards. `colour` is the colour of the rest of the
+/// clock, which is restored after each styled span.
+fn render_spans<W: Write>(
+    stdout: &mut W,
+    options: &Options,
+    spans: &[(String, Style)],
+    colour: Option<Color>,
+    layout: &Layout,
+    row: u16,
+) -> Result<(), Error> {
+    let spans = spans
+        .iter()
+        .map(|(text, style)| {
+            let lines = scale(options.font.render(text), layout.scale);
+            (lines, off_segments(options, text, layout), style)
+        })
+        .collect::<Vec<_>>();
+    let line_len = spans
+        .iter()
+        .map(|(lines, _, _)| lines[0].chars().count())
+        .sum::<usize>();
+
+    let column = layout.column(line_len);
+
+    for (i, row) in (row..row + layout.line_height).enumerate() {
+        queue!(
+            stdout,
+            MoveToRow(row),
+            Clear(ClearType::CurrentLine),
+            MoveTo(column, row),
+        )?;
+        // Cut off lines wider than the terminal rather than letting them wrap
+        let mut room = usize::from(layout.columns.saturating_sub(column));
+        for (lines, off, style) in &spans {
+            let line = lines[i].chars().take(room).collect::<String>();
+            room -= line.chars().count();
+            if let Some(colour) = style.colour {
+                queue!(stdout, SetForegroundColor(colour))?;
+            }
+            if style.dim {
+                queue!(stdout, SetAttribute(Attribute::Dim))?;
+            }
+            match off {
+                Some(off) => {
+                    let restore = style.colour.or(colour).unwrap_or(Color::Reset);
+                    print_with_off_segments(stdout, &line, &off[i], restore)?;
+                }
+                None => queue!(stdout, Print(line))?,
+            }
+            if style.colour.is_some() {
+                queue!(stdout, SetForegroundColor(colour.unwrap_or(Color::Reset)))?;
+            }
+            if style.dim {
+                // This turns off bold as well, so put the clock's attributes back
+                queue!(stdout, SetAttribute(Attribute::NormalIntensity))?;
+                options.styles.apply(stdout)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The rows of `text` with every segment of its digits lit, when `--show-off-segments`
+/// was given.
+fn off_segments(options: &Options, text: &str, layout: &Layout) -> Option<Vec<String>> {
+    options.show_off_segments.then(|| {
+        let text = text.chars().map(segment_off_char).collect::<String>();
+        scale(options.font.render(&text), layout.scale)
+    })
+}
+
+/// Enlarge `lines` so that each character becomes a `scale` × `scale` block of itself.
+pub fn scale(lines: Vec<String>, scale: u16) -> Vec<String> {
+    if scale == 1 {
+        return lines;
+    }
+    let scale = usize::from(scale);
+    lines
+        .iter()
+        .flat_map(|line| {
+            let line = line
+                .chars()
+                .flat_map(|ch| std::iter::repeat(ch).take(scale))
+                .collect::<String>();
+            std::iter::repeat(line).take(scale)
+        })
+        .collect()
+}
+
+/// The width of `spans` drawn in the font of the clock at scale 1.
+pub fn spans_width(options: &Options, spans: &[(String, Style)]) -> usize {
+    spans
+        .iter()
+        .map(|(text, _)| options.font.render(text)[0].chars().count())
+        .sum()
+}
+
+/// The text of the time, countdown, or stopwatch as it is when the clock starts.
+pub fn clock_text(options: &Options, format: &[FormatItem]) -> String {
+    match options.mode {
+        Mode::Clock => time_spans(options, format, options.now())
+            .into_iter()
+            .map(|(text, _)| text)
+            .collect(),
+        Mode::Countdown(duration) => format_duration(duration),
+        Mode::Pomodoro(cfg) => format_duration(cfg.work),
+        // Each side gets half of the width, leaving room between them
+        Mode::Chess(cfg) => {
+            let time = format_duration(cfg.time);
+            format!("{}  {}", time, time)
+        }
+        Mode::Stopwatch => format_tenths(Duration::ZERO),
+        Mode::Uptime(booted) => format_uptime(booted.elapsed()),
+        Mode::Since(moment) => format_days(elapsed_since(options.now(), options.moment(moment))),
+        Mode::Until(moment) => format_remaining(options.moment(moment) - options.now()),
+        Mode::Epoch => format_unix(options.now()),
+        Mode::Hex => format_hex_time(options.now()),
+        // The face isn't text, it's sized to the terminal instead
+        Mode::Analog => String::new(),
+    }
+}
+
+/// Print `line`, filling the gaps between lit segments with the segments of `off` drawn in
+/// `OFF_SEGMENT_COLOUR`. `colour` is the colour the rest of the line is drawn in.
+fn print_with_off_segments<W: Write>(
+    stdout: &mut W,
+    line: &str,
+    off: &str,
+    colour: Color,
+) -> Result<(), Error> {
+    for (ch, off_ch) in line.chars().zip(off.chars()) {
+        if ch == ' ' && off_ch != ' ' {
+            queue!(
+                stdout,
+                SetForegroundColor(OFF_SEGMENT_COLOUR),
+                Print(off_ch),
+                SetForegroundColor(colour)
+            )?;
+        } else {
+            queue!(stdout, Print(ch))?;
+        }
+    }
+    Ok(())
+}
+
+/// The character with every segment lit that `digit` is drawn over, other characters are
+/// left as they are.
+fn segment_off_char(digit: char) -> char {
+    if digit.is_ascii_digit() {
+        '8'
+    } else {
+        digit
+    }
+}
+
+pub fn init_screen<S: Write>(
+    screen: &mut S,
+    layout: &Layout,
+    options: &Options,
+) -> Result<(), Error> {
+    // Colours are set before clearing so that the background fills the whole screen
+    if let Some(colour) = options.colour {
+        queue!(screen, SetForegroundColor(colour))?;
+    }
+    if let Some(colour) = options.bg_colour {
+        queue!(screen, SetBackgroundColor(colour))?;
+    }
+    options.styles.apply(screen)?;
+    execute!(
+        screen,
+        Clear(ClearType::All),
+        MoveToRow(layout.time_row),
+        cursor::Hide
+    )?;
+    Ok(())
+}
+
+/// Pair each character of `line` with a colour that fades from `from` at the start of the
+/// line to `to` at the end.
+fn colour_columns(line: &str, gradient: Gradient) -> Vec<(char, Color)> {
+    let steps = line.chars().count().saturating_sub(1).max(1) as f32;
+    line.chars()
+        .enumerate()
+        .map(|(i, ch)| {
+            let t = i as f32 / steps;
+            let colour = match gradient {
+                Gradient::Between(from, to) => colour::lerp(from, to, t),
+                // Stop at violet rather than going all the way round to red again
+                Gradient::Rainbow => Color::from(colour::hsv_to_rgb(t * 300., 1., 1.)),
+            };
+            (ch, colour)
+        })
+        .collect()
+}
+
+impl RenderStats {
+    pub fn record(&mut self, time: Duration) {
+        self.last = time;
+        self.max = self.max.max(time);
+        self.total += time;
+        self.count += 1;
+    }
+
+    /// Draw the stats on the bottom row of the screen.
+    pub fn draw(&mut self, stdout: &mut Stdout, layout: &Layout) -> Result<(), Error> {
+        let mean = self.total / self.count.max(1);
+        let stats = format!(
+            "render: {}µs  max: {}µs  mean: {}µs",
+            self.last.as_micros(),
+            self.max.as_micros(),
+            mean.as_micros()
+        );
+        let row = layout.rows.saturating_sub(1);
+        queue!(
+            stdout,
+            MoveTo(0, row),
+            Clear(ClearType::CurrentLine),
+            Print(stats)
+        )?;
+        stdout.flush()?;
+        self.drawn = Some(Instant::now());
+        Ok(())
+    }
+}
+
+impl FrameBuffer {
+    /// Draw `line` centred on `row`. When it's the same width and in the same place as the
+    /// line on screen, only the runs of characters that differ are drawn.
+    pub fn draw<W: Write>(
+        &mut self,
+        stdout: &mut W,
+        line: String,
+        layout: &Layout,
+        row: u16,
+    ) -> Result<(), Error> {
+        let column = layout.column(line.chars().count());
+        // Cut off lines wider than the terminal rather than letting them wrap
+        let room = usize::from(layout.columns.saturating_sub(column));
+        let line = line.chars().take(room).collect::<String>();
+        let index = self.lines.iter().position(|&(r, _, _)| r == row);
+        match index.map(|index| &mut self.lines[index]) {
+            Some((_, col, previous))
+                if *col == column && previous.chars().count() == line.chars().count() =>
+            {
+                let mut run: Option<(usize, String)> = None;
+                let changes = previous.chars().zip(line.chars()).map(|(a, b)| (a != b, b));
+                // A trailing unchanged character ends the last run
+                for (i, (changed, ch)) in changes.chain([(false, ' ')]).enumerate() {
+                    match (&mut run, changed) {
+                        (Some((_, text)), true) => text.push(ch),
+                        (None, true) => run = Some((i, ch.to_string())),
+                        (Some((start, text)), false) => {
+                            queue!(stdout, MoveTo(column + *start as u16, row), Print(&*text))?;
+                            run = None;
+                        }
+                        (None, false) => {}
+                    }
+                }
+                *previous = line;
+            }
+            _ => {
+                queue!(
+                    stdout,
+                    MoveToRow(row),
+                    Clear(ClearType::CurrentLine),
+                    MoveTo(column, row),
+                    Print(&line)
+                )?;
+                match index {
+                    Some(index) => self.lines[index] = (row, column, line),
+                    None => self.lines.push((row, column, line)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget the lines on screen so that they're drawn in full next time.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+/// Format `now` as the number of seconds since the Unix epoch.
+fn format_unix(now: OffsetDateTime) -> String {
+    now.unix_timestamp().to_string()
+}
+
+/// Format the time of `now` as HH:MM:SS with each component as two hexadecimal digits.
+fn format_hex_time(now: OffsetDateTime) -> String {
+    format!(
+        "{:02X}:{:02X}:{:02X}",
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
+/// Format `duration` as HH:MM:SS, preceded by the number of days if it is a day or longer,
+/// e.g. 3 days 04:05:06.
+fn format_uptime(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60
+    );
+    match secs / 86_400 {
+        0 => time,
+        1 => format!("1 day {}", time),
+        days => format!("{} days {}", days, time),
+    }
+}
+
+/// The time from `since` to `now`, or zero if `since` is later.
+fn elapsed_since(now: OffsetDateTime, since: OffsetDateTime) -> Duration {
+    Duration::try_from(now - since).unwrap_or(Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::ISO_FORMAT;
+    use crate::Blink;
+
+    #[test]
+    fn lowercase_period_width() {
+        let now = time::macros::datetime!(2024-05-14 21:30 UTC);
+        let width = |lowercase_period| {
+            let options = Options {
+                lowercase_period,
+                ..Options::default()
+            };
+            let format = options.format().unwrap();
+            time_spans(&options, &format, now)
+                .iter()
+                .map(|(text, _)| segmentify_width(text))
+                .sum::<usize>()
+        };
+        assert_eq!(width(false), width(true));
+    }
+
+    #[test]
+    fn clip_wide_titles() {
+        assert_eq!(clip_title("Kitchen", 7), "Kitchen");
+        assert_eq!(clip_title("Kitchen", 6), "Kitch…");
+        // Wide characters take two columns each, so fewer fit
+        assert_eq!(clip_title("東京の時計", 10), "東京の時計");
+        assert_eq!(clip_title("東京の時計", 9), "東京の時…");
+        assert_eq!(clip_title("東京の時計", 8), "東京の…");
+        assert_eq!(clip_title("東京の時計", 8).width(), 7);
+        // Combining marks take no columns
+        assert_eq!(clip_title("cafe\u{301}", 4), "cafe\u{301}");
+    }
+
+    #[test]
+    fn iso_offset_colon() {
+        let now = time::macros::datetime!(2024-05-14 21:30:05 +10:00);
+        let mut options = Options {
+            format: Some(ISO_FORMAT.to_string()),
+            seconds_colour: Some(Color::Red),
+            ..Options::default()
+        };
+        let format = time::format_description::parse(ISO_FORMAT).unwrap();
+        let spans = time_spans(&options, &format, now);
+        assert_eq!(spans[0].0, "2024-05-14T21:30");
+        assert_eq!(spans[1].0, ":05+10:00");
+        assert_eq!(spans[1].1.colour, Some(Color::Red));
+
+        // Blinking only hides the colons between the hours, minutes, and seconds
+        options.blink = Some(Blink::OddSeconds);
+        let spans = time_spans(&options, &format, now);
+        assert_eq!(spans[0].0, "2024-05-14T21 30");
+        assert_eq!(spans[1].0, " 05+10:00");
+
+        options.blink = None;
+        options.separator = Some('.');
+        let spans = time_spans(&options, &format, now);
+        assert_eq!(spans[0].0, "2024-05-14T21.30");
+        assert_eq!(spans[1].0, ".05+10:00");
+    }
+}
@@ -0,0 +1,406 @@
+//! The state of the Pomodoro, chess clock, and stopwatch timers.
+
+use std::time::{Duration, Instant};
+
+use crossterm::style::Color;
+
+/// The length of the work and break periods of the Pomodoro timer.
+#[derive(Clone, Copy)]
+pub struct PomodoroCfg {
+    pub work: Duration,
+    pub break_: Duration,
+    /// The break taken after every fourth work period instead of the usual one.
+    pub long_break: Option<Duration>,
+    /// Draw work in red and breaks in green instead of the colour of the clock.
+    pub colours: bool,
+    /// Whether this is the interval timer, which shows the round instead of the phase.
+    pub interval: bool,
+    /// The number of work periods the interval timer stops after.
+    pub rounds: Option<u32>,
+}
+
+/// The time each player of the chess clock starts with, and the time added after each of
+/// their moves.
+#[derive(Clone, Copy)]
+pub struct ChessCfg {
+    pub time: Duration,
+    pub increment: Duration,
+}
+
+/// A chess clock, with the left side the first to move.
+pub struct Chess {
+    cfg: ChessCfg,
+    /// The time left on each side's clock when it was last stopped.
+    left: [Duration; 2],
+    /// The side whose clock runs while it's their move.
+    pub active: usize,
+    /// When the active side's clock was started, if it's running.
+    pub running_since: Option<Instant>,
+    /// The side that ran out of time and when, after which both clocks are stopped.
+    pub flag_fall: Option<(usize, Instant)>,
+}
+
+/// A Pomodoro timer counting down the current period.
+pub struct Pomodoro {
+    pub cfg: PomodoroCfg,
+    pub phase: Phase,
+    pub deadline: Instant,
+    /// The time remaining when the timer was paused, if it is paused.
+    pub paused_at: Option<Duration>,
+    /// The number of work periods finished.
+    pub completed: u32,
+}
+
+/// The period a Pomodoro timer is in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+/// A stopwatch that can be paused, reset, and record laps.
+pub struct Stopwatch {
+    started: Instant,
+    /// The elapsed time when the stopwatch was paused, if it is paused.
+    pub paused_at: Option<Duration>,
+    /// The total elapsed time at each lap.
+    pub laps: Vec<Duration>,
+    /// The number of laps the lap list has been scrolled down by.
+    pub scroll: usize,
+}
+
+impl Pomodoro {
+    pub fn new(cfg: PomodoroCfg) -> Self {
+        Pomodoro {
+            cfg,
+            phase: Phase::Work,
+            deadline: Instant::now() + cfg.work,
+            paused_at: None,
+            completed: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.paused_at
+            .unwrap_or_else(|| self.deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Switch between work and break, with the next period starting at `start`. Every
+    /// fourth break is a long one, if a long break was given.
+    pub fn next_phase(&mut self, start: Instant) {
+        let (phase, duration) = match (self.phase, self.cfg.long_break) {
+            (Phase::Work, Some(long_break)) if (self.completed + 1) % 4 == 0 => {
+                (Phase::LongBreak, long_break)
+            }
+            (Phase::Work, _) => (Phase::Break, self.cfg.break_),
+            (Phase::Break | Phase::LongBreak, _) => (Phase::Work, self.cfg.work),
+        };
+        if self.phase == Phase::Work {
+            self.completed += 1;
+        }
+        // The interval timer can have no rest
+        let (phase, duration) = match duration {
+            Duration::ZERO => (Phase::Work, self.cfg.work),
+            duration => (phase, duration),
+        };
+        self.phase = phase;
+        self.deadline = start + duration;
+        self.paused_at = None;
+    }
+
+    /// Whether this is the last work period of an interval timer with a number of rounds.
+    pub fn last_round(&self) -> bool {
+        self.phase == Phase::Work && self.cfg.rounds == Some(self.completed + 1)
+    }
+
+    /// Whether the period has ended, as long as the timer isn't paused.
+    pub fn ended(&self) -> bool {
+        self.paused_at.is_none() && self.deadline <= Instant::now()
+    }
+
+    /// End the current period early and start the next one now.
+    pub fn skip(&mut self) {
+        self.next_phase(Instant::now());
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            Some(remaining) => self.deadline = Instant::now() + remaining,
+            None => self.paused_at = Some(self.remaining()),
+        }
+    }
+
+    /// The colour of the current period, if the periods are coloured.
+    pub fn colour(&self) -> Option<Color> {
+        match self.phase {
+            _ if !self.cfg.colours => None,
+            Phase::Work => Some(Color::Red),
+            Phase::Break | Phase::LongBreak => Some(Color::Green),
+        }
+    }
+}
+
+impl Chess {
+    pub fn new(cfg: ChessCfg) -> Self {
+        Chess {
+            cfg,
+            left: [cfg.time; 2],
+            active: 0,
+            running_since: None,
+            flag_fall: None,
+        }
+    }
+
+    pub fn remaining(&self, side: usize) -> Duration {
+        match self.running_since {
+            Some(since) if side == self.active => self.left[side].saturating_sub(since.elapsed()),
+            _ => self.left[side],
+        }
+    }
+
+    fn stop(&mut self) {
+        self.left[self.active] = self.remaining(self.active);
+        self.running_since = None;
+    }
+
+    /// End the active side's move, adding the increment to their time and starting the
+    /// other side's clock. Before the first move this starts the clock instead.
+    pub fn switch(&mut self) {
+        if self.flag_fall.is_some() {
+            return;
+        }
+        if self.running_since.is_some() {
+            self.stop();
+            self.left[self.active] += self.cfg.increment;
+            self.active = 1 - self.active;
+        }
+        self.running_since = Some(Instant::now());
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.flag_fall.is_some() {
+            return;
+        }
+        match self.running_since {
+            Some(_) => self.stop(),
+            None => self.running_since = Some(Instant::now()),
+        }
+    }
+
+    /// Put both sides back to their starting time, stopped.
+    pub fn reset(&mut self) {
+        *self = Chess::new(self.cfg);
+    }
+
+    /// Stop both clocks if the active side has run out of time, returning true when it does.
+    pub fn check_flag(&mut self) -> bool {
+        if self.running_since.is_none() || !self.remaining(self.active).is_zero() {
+            return false;
+        }
+        self.stop();
+        self.flag_fall = Some((self.active, Instant::now()));
+        true
+    }
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Stopwatch {
+            started: Instant::now(),
+            paused_at: None,
+            laps: Vec::new(),
+            scroll: 0,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.paused_at.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            // Resume by moving the start forward by the time spent paused
+            Some(elapsed) => {
+                let now = Instant::now();
+                self.started = now.checked_sub(elapsed).unwrap_or(now);
+            }
+            None => self.paused_at = Some(self.started.elapsed()),
+        }
+    }
+
+    /// Reset the elapsed time to zero and clear the laps. A paused stopwatch stays paused.
+    pub fn reset(&mut self) {
+        let paused = self.paused_at.is_some();
+        *self = Stopwatch::new();
+        if paused {
+            self.paused_at = Some(Duration::ZERO);
+        }
+    }
+
+    pub fn lap(&mut self) {
+        self.laps.push(self.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pomodoro(long_break: Option<u64>, break_: u64) -> Pomodoro {
+        Pomodoro::new(PomodoroCfg {
+            work: Duration::from_secs(25 * 60),
+            break_: Duration::from_secs(break_),
+            long_break: long_break.map(Duration::from_secs),
+            colours: true,
+            interval: false,
+            rounds: None,
+        })
+    }
+
+    /// The phase after each call to `next_phase`, and its length.
+    fn phases(pomodoro: &mut Pomodoro, count: usize) -> Vec<(Phase, u32, Duration)> {
+        let start = Instant::now();
+        (0..count)
+            .map(|_| {
+                pomodoro.next_phase(start);
+                (
+                    pomodoro.phase,
+                    pomodoro.completed,
+                    pomodoro.deadline - start,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_fourth_break_is_long() {
+        let (work, short, long) = (
+            Duration::from_secs(25 * 60),
+            Duration::from_secs(5 * 60),
+            Duration::from_secs(15 * 60),
+        );
+        let mut timer = pomodoro(Some(15 * 60), 5 * 60);
+        assert!(timer.phase == Phase::Work && timer.completed == 0);
+        let phases = phases(&mut timer, 10);
+        let expected = [
+            (Phase::Break, 1, short),
+            (Phase::Work, 1, work),
+            (Phase::Break, 2, short),
+            (Phase::Work, 2, work),
+            (Phase::Break, 3, short),
+            (Phase::Work, 3, work),
+            (Phase::LongBreak, 4, long),
+            (Phase::Work, 4, work),
+            (Phase::Break, 5, short),
+            (Phase::Work, 5, work),
+        ];
+        for (i, (phase, expected)) in phases.iter().zip(expected).enumerate() {
+            assert!(*phase == expected, "phase {}", i + 1);
+        }
+    }
+
+    #[test]
+    fn no_long_break_without_one_given() {
+        let mut timer = pomodoro(None, 5 * 60);
+        let phases = phases(&mut timer, 8);
+        assert!(phases
+            .iter()
+            .all(|(phase, _, _)| *phase != Phase::LongBreak));
+        assert!(phases[6].0 == Phase::Break && phases[6].1 == 4);
+    }
+
+    #[test]
+    fn next_phase_resumes() {
+        let mut timer = pomodoro(None, 5 * 60);
+        timer.toggle_pause();
+        assert!(timer.paused_at.is_some());
+        timer.skip();
+        assert!(timer.phase == Phase::Break);
+        assert!(timer.paused_at.is_none());
+    }
+
+    #[test]
+    fn interval_rounds() {
+        let mut timer = pomodoro(None, 0);
+        timer.cfg.interval = true;
+        timer.cfg.rounds = Some(3);
+        // Without a rest, each round goes straight to the next
+        assert!(!timer.last_round());
+        let phases = phases(&mut timer, 2);
+        assert!(phases.iter().all(|(phase, _, _)| *phase == Phase::Work));
+        assert_eq!(timer.completed, 2);
+        assert!(timer.last_round());
+    }
+
+    fn chess(minutes: u64, increment: u64) -> Chess {
+        Chess::new(ChessCfg {
+            time: Duration::from_secs(minutes * 60),
+            increment: Duration::from_secs(increment),
+        })
+    }
+
+    /// Start the active side's clock `secs` ago.
+    fn started_ago(chess: &mut Chess, secs: u64) {
+        chess.running_since = Some(Instant::now() - Duration::from_secs(secs));
+    }
+
+    #[test]
+    fn switching_sides() {
+        let mut clock = chess(5, 0);
+        // The first switch starts the left side's clock
+        clock.switch();
+        assert_eq!(clock.active, 0);
+        assert!(clock.running_since.is_some());
+        started_ago(&mut clock, 10);
+        clock.switch();
+        assert_eq!(clock.active, 1);
+        let left = clock.remaining(0);
+        assert!(left <= Duration::from_secs(290) && left > Duration::from_secs(289));
+        // The right side's clock is running from where it started
+        assert!(clock.remaining(1) > Duration::from_secs(299));
+        started_ago(&mut clock, 20);
+        clock.switch();
+        assert_eq!(clock.active, 0);
+        let right = clock.remaining(1);
+        assert!(right <= Duration::from_secs(280) && right > Duration::from_secs(279));
+        // Only the active side's clock runs
+        assert!(clock.remaining(0) <= left && clock.remaining(0) > left - Duration::from_secs(1));
+    }
+
+    #[test]
+    fn increments() {
+        let mut clock = chess(5, 3);
+        clock.switch();
+        started_ago(&mut clock, 10);
+        clock.switch();
+        let left = clock.remaining(0);
+        assert!(left <= Duration::from_secs(293) && left > Duration::from_secs(292));
+        // The increment is added when a move ends, not when the clock starts
+        assert!(clock.remaining(1) <= Duration::from_secs(300));
+    }
+
+    #[test]
+    fn flag_fall() {
+        let mut clock = chess(1, 3);
+        // A stopped clock can't flag
+        assert!(!clock.check_flag());
+        clock.switch();
+        assert!(!clock.check_flag());
+        started_ago(&mut clock, 61);
+        assert!(clock.check_flag());
+        assert!(matches!(clock.flag_fall, Some((0, _))));
+        assert!(clock.running_since.is_none());
+        assert_eq!(clock.remaining(0), Duration::ZERO);
+        // Both clocks stay stopped, without the increment
+        clock.switch();
+        clock.toggle_pause();
+        assert!(clock.running_since.is_none());
+        assert_eq!(clock.active, 0);
+        assert_eq!(clock.remaining(0), Duration::ZERO);
+        assert!(!clock.check_flag());
+        clock.reset();
+        assert!(clock.flag_fall.is_none());
+        assert_eq!(clock.remaining(0), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,608 @@
+//! Time zone support backed by the system's compiled (TZif) time zone database.
+//!
+//! Only the parts needed to work out the UTC offset in effect at a given instant are
+//! implemented: the transition table and the POSIX TZ rule in the footer that
+//! describes transitions after the end of the table, see RFC 8536.
+//!
+//! The database is only read on Unix-like systems, elsewhere only UTC is available.
+
+use std::fmt::{Display, Formatter};
+use std::path::{Component, Path, PathBuf};
+
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+const DEFAULT_TZDIR: &str = "/usr/share/zoneinfo";
+
+pub struct TimeZone {
+    name: String,
+    transitions: Vec<i64>,
+    transition_types: Vec<usize>,
+    offsets: Vec<i32>,
+    rule: Option<Rule>,
+}
+
+#[derive(Debug)]
+pub enum TzError {
+    InvalidName(String),
+    Io(String, std::io::Error),
+    Malformed(String),
+    /// There's no time zone database to read on this system.
+    Unsupported(String),
+}
+
+/// A POSIX TZ rule, as found in the footer of version 2+ TZif files.
+struct Rule {
+    std_offset: i32,
+    dst: Option<Dst>,
+}
+
+struct Dst {
+    offset: i32,
+    start: Transition,
+    end: Transition,
+}
+
+/// When a daylight saving transition happens, the time is seconds after local midnight.
+struct Transition {
+    day: TransitionDay,
+    time: i32,
+}
+
+enum TransitionDay {
+    /// Jn: day of the year 1–365, February 29 is never counted.
+    Julian(u16),
+    /// n: zero-based day of the year 0–365, February 29 is counted in leap years.
+    Ordinal(u16),
+    /// Mm.w.d: day d (0 = Sunday) of week w (5 = last) of month m.
+    MonthWeekDay(u8, u8, u8),
+}
+
+impl TimeZone {
+    /// Load the time zone with the given IANA name, e.g. `Australia/Brisbane`.
+    ///
+    /// The database is looked up in `$TZDIR`, falling back to `/usr/share/zoneinfo`.
+    /// Other systems have no database, so only UTC can be loaded on them.
+    pub fn named(name: &str) -> Result<TimeZone, TzError> {
+        let relative = Path::new(name);
+        if name.is_empty()
+            || relative
+                .components()
+                .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(TzError::InvalidName(name.to_string()));
+        }
+
+        if !cfg!(unix) {
+            return match name {
+                "UTC" | "Etc/UTC" => Ok(TimeZone::utc(name)),
+                _ => Err(TzError::Unsupported(name.to_string())),
+            };
+        }
+        let dir = std::env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_TZDIR));
+        TimeZone::load(&dir, name)
+    }
+
+    /// Load the time zone with the given name from the database in `dir`.
+    fn load(dir: &Path, name: &str) -> Result<TimeZone, TzError> {
+        let path = dir.join(name);
+        // Areas like America are directories of time zones rather than time zones
+        if path.is_dir() {
+            return Err(TzError::InvalidName(name.to_string()));
+        }
+        let data = match std::fs::read(path) {
+            Ok(data) => data,
+            // UTC is always available, even without a time zone database
+            Err(_) if matches!(name, "UTC" | "Etc/UTC") => return Ok(TimeZone::utc(name)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Err(TzError::InvalidName(name.to_string()))
+            }
+            Err(err) => return Err(TzError::Io(name.to_string(), err)),
+        };
+
+        parse_tzif(name, &data).ok_or_else(|| TzError::Malformed(name.to_string()))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn utc(name: &str) -> TimeZone {
+        TimeZone {
+            name: name.to_string(),
+            transitions: Vec::new(),
+            transition_types: Vec::new(),
+            offsets: vec![0],
+            rule: None,
+        }
+    }
+
+    /// The current time in this time zone.
+    ///
+    /// The offset is looked up on each call so that daylight saving transitions are
+    /// picked up by a long running clock.
+    pub fn now(&self) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+        let offset = self.offset_at(now.unix_timestamp());
+        // Offsets are always in range for real time zones, fall back to UTC if not
+        now.to_offset(UtcOffset::from_whole_seconds(offset).unwrap_or(UtcOffset::UTC))
+    }
+
+    /// The date and time `local` in this time zone, with the offset in effect then.
+    ///
+    /// Times skipped or repeated by a transition get the offset from one side of it.
+    pub fn assume(&self, local: PrimitiveDateTime) -> OffsetDateTime {
+        let local = local.assume_utc();
+        // The offset at the local time read as UTC is off by at most one transition
+        let guess = self.offset_at(local.unix_timestamp());
+        let offset = self.offset_at(local.unix_timestamp() - i64::from(guess));
+        let offset = UtcOffset::from_whole_seconds(offset).unwrap_or(UtcOffset::UTC);
+        local.replace_offset(offset)
+    }
+
+    /// The UTC offset in seconds in effect at `timestamp` (seconds since the Unix epoch).
+    fn offset_at(&self, timestamp: i64) -> i32 {
+        match self.transitions.iter().rposition(|&t| t <= timestamp) {
+            Some(i) if i + 1 < self.transitions.len() || self.rule.is_none() => {
+                self.offsets[self.transition_types[i]]
+            }
+            // Before the first transition time type 0 is in effect (RFC 8536 section 3.2),
+            // which is usually the local mean time
+            None if !self.transitions.is_empty() || self.rule.is_none() => self.offsets[0],
+            _ => self
+                .rule
+                .as_ref()
+                .map_or(0, |rule| rule.offset_at(timestamp)),
+        }
+    }
+}
+
+impl Rule {
+    fn offset_at(&self, timestamp: i64) -> i32 {
+        let dst = match &self.dst {
+            Some(dst) => dst,
+            None => return self.std_offset,
+        };
+
+        let year = match OffsetDateTime::from_unix_timestamp(timestamp + i64::from(self.std_offset))
+        {
+            Ok(local) => local.year(),
+            Err(_) => return self.std_offset,
+        };
+        // The start of DST is expressed in standard time, the end in daylight time
+        let start = dst.start.timestamp(year) - i64::from(self.std_offset);
+        let end = dst.end.timestamp(year) - i64::from(dst.offset);
+
+        let in_dst = if start < end {
+            (start..end).contains(&timestamp)
+        } else {
+            // Southern hemisphere: DST spans the new year
+            timestamp < end || timestamp >= start
+        };
+        if in_dst {
+            dst.offset
+        } else {
+            self.std_offset
+        }
+    }
+}
+
+impl Transition {
+    /// Local seconds since the epoch of this transition in `year`.
+    fn timestamp(&self, year: i32) -> i64 {
+        let date = match self.day {
+            TransitionDay::Julian(n) => {
+                // February 29 is skipped, so days after it shift by one in leap years
+                let leap = time::util::is_leap_year(year) && n > 59;
+                Date::from_ordinal_date(year, n + u16::from(leap)).ok()
+            }
+            TransitionDay::Ordinal(n) => Date::from_ordinal_date(year, n + 1).ok(),
+            TransitionDay::MonthWeekDay(month, week, weekday) => {
+                month_week_day(year, month, week, weekday)
+            }
+        };
+        let midnight = date.map_or(0, |date| date.midnight().assume_utc().unix_timestamp());
+        midnight + i64::from(self.time)
+    }
+}
+
+fn month_week_day(year: i32, month: u8, week: u8, weekday: u8) -> Option<Date> {
+    let month = Month::try_from(month).ok()?;
+    let first = Date::from_calendar_date(year, month, 1).ok()?;
+    let first_weekday = first.weekday().number_days_from_sunday();
+    let mut day = 1 + (7 + weekday - first_weekday) % 7 + (week - 1) * 7;
+    let days_in_month = time::util::days_in_year_month(year, month);
+    while day > days_in_month {
+        day -= 7;
+    }
+    Date::from_calendar_date(year, month, day).ok()
+}
+
+fn parse_tzif(name: &str, data: &[u8]) -> Option<TimeZone> {
+    let mut reader = Reader { data };
+    let header = reader.header()?;
+    if header.version < 2 {
+        let (transitions, transition_types, offsets) = reader.body(&header, 4)?;
+        return Some(TimeZone {
+            name: name.to_string(),
+            transitions,
+            transition_types,
+            offsets,
+            rule: None,
+        });
+    }
+
+    // Skip the version 1 data block, the 64-bit block that follows supersedes it
+    reader.take(header.body_len(4))?;
+    let header = reader.header()?;
+    let (transitions, transition_types, offsets) = reader.body(&header, 8)?;
+    let footer = std::str::from_utf8(reader.data).ok()?;
+    let rule = footer
+        .trim_matches('\n')
+        .lines()
+        .next()
+        .filter(|rule| !rule.is_empty())
+        .and_then(parse_rule);
+
+    Some(TimeZone {
+        name: name.to_string(),
+        transitions,
+        transition_types,
+        offsets,
+        rule,
+    })
+}
+
+struct Header {
+    version: u8,
+    isutcnt: usize,
+    isstdcnt: usize,
+    leapcnt: usize,
+    timecnt: usize,
+    typecnt: usize,
+    charcnt: usize,
+}
+
+impl Header {
+    fn body_len(&self, time_size: usize) -> usize {
+        self.timecnt * time_size
+            + self.timecnt
+            + self.typecnt * 6
+            + self.charcnt
+            + self.leapcnt * (time_size + 4)
+            + self.isstdcnt
+            + self.isutcnt
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn header(&mut self) -> Option<Header> {
+        let header = self.take(20)?;
+        if &header[..4] != b"TZif" {
+            return None;
+        }
+        let version = match header[4] {
+            0 => 1,
+            b'2'..=b'9' => header[4] - b'0',
+            _ => return None,
+        };
+        let mut count = || self.u32().map(|n| n as usize);
+        Some(Header {
+            version,
+            isutcnt: count()?,
+            isstdcnt: count()?,
+            leapcnt: count()?,
+            timecnt: count()?,
+            typecnt: count()?,
+            charcnt: count()?,
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn body(
+        &mut self,
+        header: &Header,
+        time_size: usize,
+    ) -> Option<(Vec<i64>, Vec<usize>, Vec<i32>)> {
+        let transitions = self
+            .take(header.timecnt * time_size)?
+            .chunks(time_size)
+            .map(|chunk| match time_size {
+                4 => i64::from(i32::from_be_bytes(chunk.try_into().unwrap())),
+                _ => i64::from_be_bytes(chunk.try_into().unwrap()),
+            })
+            .collect();
+        let transition_types = self
+            .take(header.timecnt)?
+            .iter()
+            .map(|&i| usize::from(i))
+            .collect::<Vec<_>>();
+        let offsets = self
+            .take(header.typecnt * 6)?
+            .chunks(6)
+            .map(|ttinfo| i32::from_be_bytes(ttinfo[..4].try_into().unwrap()))
+            .collect::<Vec<_>>();
+        if offsets.is_empty() || transition_types.iter().any(|&i| i >= offsets.len()) {
+            return None;
+        }
+        self.take(header.charcnt + header.leapcnt * (time_size + 4))?;
+        self.take(header.isstdcnt + header.isutcnt)?;
+        Some((transitions, transition_types, offsets))
+    }
+}
+
+/// Parse a POSIX TZ rule such as `AEST-10AEDT,M10.1.0,M4.1.0/3`.
+fn parse_rule(rule: &str) -> Option<Rule> {
+    let mut rest = rule;
+    parse_abbreviation(&mut rest)?;
+    let std_offset = parse_duration(&mut rest)?.checked_neg()?;
+    if rest.is_empty() {
+        return Some(Rule {
+            std_offset,
+            dst: None,
+        });
+    }
+
+    parse_abbreviation(&mut rest)?;
+    let offset = if rest.starts_with(',') {
+        std_offset.checked_add(3600)?
+    } else {
+        parse_duration(&mut rest)?.checked_neg()?
+    };
+    rest = rest.strip_prefix(',')?;
+    let (start, end) = rest.split_once(',')?;
+    Some(Rule {
+        std_offset,
+        dst: Some(Dst {
+            offset,
+            start: parse_transition(start)?,
+            end: parse_transition(end)?,
+        }),
+    })
+}
+
+fn parse_abbreviation(s: &mut &str) -> Option<()> {
+    let len = if let Some(quoted) = s.strip_prefix('<') {
+        quoted.find('>')? + 2
+    } else {
+        s.find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(s.len())
+    };
+    if len < 3 {
+        return None;
+    }
+    *s = &s[len..];
+    Some(())
+}
+
+/// Parse `[+-]hh[:mm[:ss]]` returning the number of seconds.
+fn parse_duration(s: &mut &str) -> Option<i32> {
+    let end = s
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, ':' | '+' | '-')))
+        .unwrap_or(s.len());
+    let (duration, rest) = s.split_at(end);
+    *s = rest;
+
+    let (sign, duration) = match duration.strip_prefix('-') {
+        Some(duration) => (-1, duration),
+        None => (1, duration.strip_prefix('+').unwrap_or(duration)),
+    };
+    let mut seconds = 0i32;
+    for (part, scale) in duration.split(':').zip([3600, 60, 1]) {
+        let part = part.parse::<i32>().ok()?.checked_mul(scale)?;
+        seconds = seconds.checked_add(part)?;
+    }
+    Some(sign * seconds)
+}
+
+fn parse_transition(s: &str) -> Option<Transition> {
+    let (day, time) = match s.split_once('/') {
+        Some((day, mut time)) => (day, parse_duration(&mut time)?),
+        None => (s, 2 * 3600),
+    };
+    let day = if let Some(n) = day.strip_prefix('J') {
+        TransitionDay::Julian(n.parse().ok().filter(|n| (1..=365).contains(n))?)
+    } else if let Some(mwd) = day.strip_prefix('M') {
+        let mut parts = mwd.split('.').map(|part| part.parse::<u8>().ok());
+        let (month, week, weekday) = (parts.next()??, parts.next()??, parts.next()??);
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return None;
+        }
+        TransitionDay::MonthWeekDay(month, week, weekday)
+    } else {
+        TransitionDay::Ordinal(day.parse().ok().filter(|&n| n <= 365)?)
+    };
+    Some(Transition { day, time })
+}
+
+impl Display for TzError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TzError::InvalidName(name) => write!(f, "unknown time zone: '{name}'"),
+            TzError::Io(name, err) => write!(f, "unable to read time zone '{name}': {err}"),
+            TzError::Malformed(name) => write!(f, "invalid time zone data for '{name}'"),
+            TzError::Unsupported(name) => write!(
+                f,
+                "unable to load time zone '{name}', time zones other than UTC are only \
+                available on Unix-like systems"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TzError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    fn offset_at(rule: &Rule, at: OffsetDateTime) -> i32 {
+        rule.offset_at(at.unix_timestamp())
+    }
+
+    #[test]
+    fn rule_without_dst() {
+        let rule = parse_rule("AEST-10").unwrap();
+        assert_eq!(offset_at(&rule, datetime!(2024-01-01 00:00 UTC)), 10 * 3600);
+        let rule = parse_rule("<+0530>-5:30").unwrap();
+        assert_eq!(
+            offset_at(&rule, datetime!(2024-07-01 00:00 UTC)),
+            5 * 3600 + 1800
+        );
+    }
+
+    #[test]
+    fn northern_hemisphere_dst() {
+        for rule in ["EST5EDT,M3.2.0,M11.1.0", "EST5EDT,M3.2.0/2,M11.1.0/2"] {
+            let rule = parse_rule(rule).unwrap();
+            let (est, edt) = (-5 * 3600, -4 * 3600);
+            assert_eq!(offset_at(&rule, datetime!(2024-01-15 12:00 UTC)), est);
+            assert_eq!(offset_at(&rule, datetime!(2024-07-15 12:00 UTC)), edt);
+            // 2am EST on the second Sunday of March
+            assert_eq!(offset_at(&rule, datetime!(2024-03-10 06:59:59 UTC)), est);
+            assert_eq!(offset_at(&rule, datetime!(2024-03-10 07:00 UTC)), edt);
+            // 2am EDT on the first Sunday of November
+            assert_eq!(offset_at(&rule, datetime!(2024-11-03 05:59:59 UTC)), edt);
+            assert_eq!(offset_at(&rule, datetime!(2024-11-03 06:00 UTC)), est);
+        }
+    }
+
+    #[test]
+    fn southern_hemisphere_dst() {
+        let rule = parse_rule("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        let (aest, aedt) = (10 * 3600, 11 * 3600);
+        assert_eq!(offset_at(&rule, datetime!(2024-01-15 00:00 UTC)), aedt);
+        assert_eq!(offset_at(&rule, datetime!(2024-07-15 00:00 UTC)), aest);
+        // 3am AEDT on the first Sunday of April
+        assert_eq!(offset_at(&rule, datetime!(2024-04-06 15:59:59 UTC)), aedt);
+        assert_eq!(offset_at(&rule, datetime!(2024-04-06 16:00 UTC)), aest);
+        // 2am AEST on the first Sunday of October
+        assert_eq!(offset_at(&rule, datetime!(2024-10-05 15:59:59 UTC)), aest);
+        assert_eq!(offset_at(&rule, datetime!(2024-10-05 16:00 UTC)), aedt);
+    }
+
+    #[test]
+    fn last_week_of_month() {
+        // Central European Time changes on the last Sunday of March and October
+        let rule = parse_rule("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_eq!(offset_at(&rule, datetime!(2024-03-31 00:59:59 UTC)), 3600);
+        assert_eq!(offset_at(&rule, datetime!(2024-03-31 01:00 UTC)), 7200);
+        assert_eq!(offset_at(&rule, datetime!(2024-10-27 00:59:59 UTC)), 7200);
+        assert_eq!(offset_at(&rule, datetime!(2024-10-27 01:00 UTC)), 3600);
+    }
+
+    #[test]
+    fn julian_and_ordinal_days() {
+        let start = parse_transition("J60").unwrap();
+        // J60 is March 1 even in leap years
+        assert_eq!(
+            start.timestamp(2024),
+            datetime!(2024-03-01 02:00 UTC).unix_timestamp()
+        );
+        let start = parse_transition("59/0").unwrap();
+        // Day 59 counts February 29 in leap years
+        assert_eq!(
+            start.timestamp(2024),
+            datetime!(2024-02-29 00:00 UTC).unix_timestamp()
+        );
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(parse_rule("EST").is_none());
+        assert!(parse_rule("E5").is_none());
+        assert!(parse_rule("EST5EDT,M13.1.0,M11.1.0").is_none());
+        assert!(parse_rule("EST5EDT,M3.6.0,M11.1.0").is_none());
+        assert!(parse_rule("EST5EDT,M3.2.7,M11.1.0").is_none());
+        assert!(parse_rule("EST5EDT,M3.2.0").is_none());
+    }
+
+    #[test]
+    fn overflowing_durations() {
+        assert!(parse_rule("EST99999999999").is_none());
+        assert!(parse_rule("EST999999999").is_none());
+        assert!(parse_rule("EST5EDT,M3.2.0/2:99999999,M11.1.0").is_none());
+    }
+
+    /// Load `name` from the copy of the database in testdata.
+    fn fixture(name: &str) -> TimeZone {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/zoneinfo");
+        TimeZone::load(&dir, name).unwrap()
+    }
+
+    fn offset(zone: &TimeZone, at: OffsetDateTime) -> i32 {
+        zone.offset_at(at.unix_timestamp())
+    }
+
+    #[test]
+    fn zoneinfo_file() {
+        let berlin = fixture("Europe/Berlin");
+        let (cet, cest) = (3600, 2 * 3600);
+        assert_eq!(offset(&berlin, datetime!(2024-01-15 12:00 UTC)), cet);
+        assert_eq!(offset(&berlin, datetime!(2024-07-15 12:00 UTC)), cest);
+        // 1am UTC on the last Sunday of March and October
+        assert_eq!(offset(&berlin, datetime!(2024-03-31 00:59:59 UTC)), cet);
+        assert_eq!(offset(&berlin, datetime!(2024-03-31 01:00 UTC)), cest);
+        assert_eq!(offset(&berlin, datetime!(2024-10-27 00:59:59 UTC)), cest);
+        assert_eq!(offset(&berlin, datetime!(2024-10-27 01:00 UTC)), cet);
+        // Past the end of the table, where the rule in the footer takes over
+        assert_eq!(offset(&berlin, datetime!(2100-01-15 12:00 UTC)), cet);
+        assert_eq!(offset(&berlin, datetime!(2100-07-15 12:00 UTC)), cest);
+        // Double summer time after the war
+        assert_eq!(offset(&berlin, datetime!(1947-06-01 12:00 UTC)), 3 * 3600);
+    }
+
+    #[test]
+    fn before_the_first_transition() {
+        // Time type 0, local mean time, rather than the first standard time
+        let berlin = fixture("Europe/Berlin");
+        assert_eq!(
+            offset(&berlin, datetime!(1850-01-01 00:00 UTC)),
+            53 * 60 + 28
+        );
+        let brisbane = fixture("Australia/Brisbane");
+        assert_eq!(
+            offset(&brisbane, datetime!(1850-01-01 00:00 UTC)),
+            10 * 3600 + 12 * 60 + 8
+        );
+        // Queensland had daylight saving for a few summers only
+        assert_eq!(
+            offset(&brisbane, datetime!(1990-01-01 00:00 UTC)),
+            11 * 3600
+        );
+        assert_eq!(
+            offset(&brisbane, datetime!(2024-01-01 00:00 UTC)),
+            10 * 3600
+        );
+        assert_eq!(fixture("Australia/Brisbane").name(), "Australia/Brisbane");
+    }
+
+    #[test]
+    fn directory_is_not_a_time_zone() {
+        let dir = std::env::temp_dir().join(format!("7clock-tz-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("America")).unwrap();
+        let result = TimeZone::load(&dir, "America");
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(result, Err(TzError::InvalidName(_))));
+    }
+}